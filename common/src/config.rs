@@ -36,8 +36,265 @@ pub struct ProcessorConfig {
     pub log_level: String,
     #[serde(default)]
     pub matcher_configs: Option<std::collections::HashMap<String, (i32, i32)>>,
+    /// Normalizers applied in order to the values of a matcher before they are matched.
+    #[serde(default)]
+    pub matcher_normalizers: std::collections::HashMap<String, Vec<NormalizerConfig>>,
+    /// Approximate matchers keyed by the name of the matcher they produce.
+    #[serde(default)]
+    pub fuzzy_matchers: std::collections::HashMap<String, FuzzyMatcherConfig>,
+    /// Proximity matchers keyed by the name of the matcher they produce.
+    #[serde(default)]
+    pub geo_matchers: std::collections::HashMap<String, GeoMatcherConfig>,
+    /// Simple features of neighbors handed to `extract_graph_features`, e.g. for velocity sums.
+    #[serde(default)]
+    pub neighbor_features: Vec<String>,
     #[serde(default = "default_processor_metrics_address")]
     pub metrics_address: String,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: i32,
+    #[serde(default = "default_batch_concurrency")]
+    pub batch_concurrency: usize,
+    #[serde(default = "default_visibility_timeout_seconds")]
+    pub visibility_timeout_seconds: i32,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: i32,
+    #[serde(default = "default_retry_backoff_base_seconds")]
+    pub retry_backoff_base_seconds: i32,
+    #[serde(default = "default_retry_backoff_max_seconds")]
+    pub retry_backoff_max_seconds: i32,
+    #[serde(default = "default_worker_restart_backoff_base_seconds")]
+    pub worker_restart_backoff_base_seconds: i32,
+    #[serde(default = "default_worker_restart_backoff_max_seconds")]
+    pub worker_restart_backoff_max_seconds: i32,
+    #[serde(default = "default_reconciliation_interval_seconds")]
+    pub reconciliation_interval_seconds: u64,
+    #[serde(default = "default_reconciliation_grace_seconds")]
+    pub reconciliation_grace_seconds: i64,
+    #[serde(default = "default_reconciliation_batch_size")]
+    pub reconciliation_batch_size: i64,
+    /// How often active scoring channels are checked for changes.
+    #[serde(default = "default_channel_reload_interval_seconds")]
+    pub channel_reload_interval_seconds: u64,
+    #[serde(default)]
+    pub graph_filter: GraphFilterConfig,
+    /// Traversal of newly imported transactions.
+    #[serde(default)]
+    pub process_traversal: TraversalConfig,
+    /// Traversal of neighbors queued for recalculation.
+    #[serde(default)]
+    pub recalc_traversal: TraversalConfig,
+    #[serde(default)]
+    pub recalc_fanout: RecalcFanoutConfig,
+    /// Traversal from labeled transactions to the neighbors queued for recalculation,
+    /// `max_depth` bounds how far a label propagates.
+    #[serde(default)]
+    pub label_traversal: TraversalConfig,
+    #[serde(default)]
+    pub hubs: HubConfig,
+    #[serde(default)]
+    pub match_node_storage: MatchNodeStorageConfig,
+}
+
+/// A matcher linking similar rather than equal values. Each value of `source` is reduced to
+/// blocking keys that are matched exactly, candidates sharing a key are linked with the matcher
+/// confidence scaled by the similarity of their values.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct FuzzyMatcherConfig {
+    pub source: String,
+    pub blocking: BlockingKeyConfig,
+    pub confidence: i32,
+    pub importance: i32,
+    /// Candidates less similar than this are not linked.
+    #[serde(default = "default_min_similarity")]
+    pub min_similarity: f64,
+    /// Blocking keys shared by more payload numbers are skipped like hubs, a key as common as an
+    /// email domain or a frequent n-gram would compare every transaction with a large share of all.
+    #[serde(default = "default_max_block_size")]
+    pub max_block_size: usize,
+}
+
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockingKeyConfig {
+    /// Double Metaphone codes of every word, similarity by Jaro-Winkler.
+    Phonetic,
+    /// Character n-grams of the value, similarity by normalized Levenshtein distance.
+    Ngram {
+        #[serde(default = "default_ngram_size")]
+        size: usize,
+    },
+    /// The email domain, similarity of the local parts by Jaro-Winkler.
+    EmailDomain,
+}
+
+/// A matcher linking transactions whose coordinates are close. The location of the fields of
+/// the `sources` matchers is indexed, transactions within `radius_km` of each other are linked
+/// with a confidence decaying linearly from `confidence` to zero at the radius.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct GeoMatcherConfig {
+    pub sources: Vec<String>,
+    pub radius_km: f64,
+    pub confidence: i32,
+    pub importance: i32,
+    /// Nearest points looked up per point of a payload, a dense area would otherwise link every
+    /// transaction within the radius.
+    #[serde(default = "default_max_geo_neighbors")]
+    pub max_neighbors: usize,
+}
+
+/// A step of a matcher's normalization pipeline.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalizerConfig {
+    /// Trims the value and collapses inner whitespace.
+    Trim,
+    Lowercase,
+    /// Compatibility decomposition with diacritics removed, "Müller" becomes "Muller".
+    UnicodeFold,
+    /// Lowercased email, Gmail addresses also lose dots and the "+tag" of the local part.
+    Email,
+    /// E.164 phone number, numbers without a country prefix get `default_country_code`.
+    Phone {
+        #[serde(default)]
+        default_country_code: Option<String>,
+    },
+    /// Folded, lowercased address with punctuation removed and street types abbreviated.
+    Address,
+}
+
+/// Layout of match node links. The most recent links stay inline on the node, older ones are
+/// moved into bucket documents so popular nodes stay below the document size limit.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct MatchNodeStorageConfig {
+    /// Links kept inline on the node.
+    #[serde(default = "default_inline_links")]
+    pub inline_links: usize,
+    /// Links per overflow bucket.
+    #[serde(default = "default_bucket_size")]
+    pub bucket_size: usize,
+    /// Payload numbers kept on the node, at least the hub threshold. A node reaching it stays a
+    /// hub and later payload numbers are only recorded in its links.
+    #[serde(default = "default_max_payload_numbers")]
+    pub max_payload_numbers: usize,
+}
+
+impl Default for MatchNodeStorageConfig {
+    fn default() -> Self {
+        Self {
+            inline_links: default_inline_links(),
+            bucket_size: default_bucket_size(),
+            max_payload_numbers: default_max_payload_numbers(),
+        }
+    }
+}
+
+/// Handling of match nodes shared by so many transactions that they stop meaning anything,
+/// e.g. a default device token or a placeholder email.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct HubConfig {
+    /// A node linking at least this many payload numbers becomes a hub.
+    #[serde(default = "default_hub_threshold")]
+    pub threshold: usize,
+    #[serde(default)]
+    pub mode: HubMode,
+    /// Confidence multiplier of hub nodes in `HubMode::DownWeight`.
+    #[serde(default = "default_hub_down_weight")]
+    pub down_weight: f64,
+}
+
+impl Default for HubConfig {
+    fn default() -> Self {
+        Self {
+            threshold: default_hub_threshold(),
+            mode: HubMode::default(),
+            down_weight: default_hub_down_weight(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HubMode {
+    /// Hubs are not traversed and produce no direct connections.
+    #[default]
+    Exclude,
+    /// Hubs are traversed with their confidence scaled by `down_weight`.
+    DownWeight,
+}
+
+/// Limits which neighbors of a processed transaction are queued for recalculation.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct RecalcFanoutConfig {
+    /// Neighbors created before this date are never recalculated.
+    #[serde(default)]
+    pub cutoff_date: Option<chrono::DateTime<chrono::Utc>>,
+    /// Neighbors created more than this many days ago are not recalculated.
+    #[serde(default)]
+    pub max_age_days: Option<i64>,
+    /// At most this many neighbors are queued per processed transaction, strongest links first.
+    #[serde(default)]
+    pub max_neighbors: Option<usize>,
+    /// Labeled neighbors already have a verdict and are not recalculated.
+    #[serde(default)]
+    pub skip_labeled: bool,
+}
+
+/// Bounds of the connected transactions search.
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct TraversalConfig {
+    /// Number of hops followed past the root's own match nodes, see `find_connected_transactions`.
+    #[serde(default = "default_traversal_max_depth")]
+    pub max_depth: i32,
+    #[serde(default = "default_traversal_limit")]
+    pub limit: i32,
+    #[serde(default = "default_traversal_min_confidence")]
+    pub min_confidence: i32,
+    /// Overrides keyed by matcher name.
+    #[serde(default)]
+    pub matchers: std::collections::HashMap<String, MatcherTraversalConfig>,
+}
+
+impl Default for TraversalConfig {
+    fn default() -> Self {
+        Self {
+            max_depth: default_traversal_max_depth(),
+            limit: default_traversal_limit(),
+            min_confidence: default_traversal_min_confidence(),
+            matchers: std::collections::HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct MatcherTraversalConfig {
+    /// Nodes of this matcher are not followed past this depth.
+    #[serde(default)]
+    pub max_depth: Option<i32>,
+    /// Nodes of this matcher below this confidence are not followed.
+    #[serde(default)]
+    pub min_confidence: Option<i32>,
+}
+
+/// Restricts the links followed when searching connected transactions. Time and distance limits
+/// are measured against the root transaction; links without a timestamp or location are kept.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct GraphFilterConfig {
+    /// Skip linked transactions that happened more than this before the root.
+    #[serde(default)]
+    pub max_age_seconds: Option<i64>,
+    /// Skip linked transactions more than this before or after the root. The window is centered
+    /// on the root for every hop, consecutive hops are not compared with each other.
+    #[serde(default)]
+    pub root_time_window_seconds: Option<i64>,
+    /// Skip linked transactions located further than this from the root.
+    #[serde(default)]
+    pub max_distance_km: Option<f64>,
+    /// When set, only these matchers are traversed.
+    #[serde(default)]
+    pub allowed_matchers: Option<Vec<String>>,
+    /// Matchers that are never traversed.
+    #[serde(default)]
+    pub denied_matchers: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Clone, Default)]
@@ -64,3 +321,100 @@ fn default_metrics_path() -> String {
 fn default_processor_metrics_address() -> String {
     "0.0.0.0:9100".to_string()
 }
+
+fn default_batch_size() -> i32 {
+    10
+}
+
+fn default_batch_concurrency() -> usize {
+    4
+}
+
+fn default_visibility_timeout_seconds() -> i32 {
+    30
+}
+
+fn default_max_retries() -> i32 {
+    5
+}
+
+fn default_retry_backoff_base_seconds() -> i32 {
+    5
+}
+
+fn default_retry_backoff_max_seconds() -> i32 {
+    600
+}
+
+fn default_worker_restart_backoff_base_seconds() -> i32 {
+    1
+}
+
+fn default_worker_restart_backoff_max_seconds() -> i32 {
+    60
+}
+
+fn default_reconciliation_interval_seconds() -> u64 {
+    60
+}
+
+fn default_reconciliation_grace_seconds() -> i64 {
+    300
+}
+
+fn default_reconciliation_batch_size() -> i64 {
+    500
+}
+
+fn default_channel_reload_interval_seconds() -> u64 {
+    30
+}
+
+fn default_traversal_max_depth() -> i32 {
+    10
+}
+
+fn default_traversal_limit() -> i32 {
+    200
+}
+
+fn default_traversal_min_confidence() -> i32 {
+    50
+}
+
+fn default_hub_threshold() -> usize {
+    1000
+}
+
+fn default_hub_down_weight() -> f64 {
+    0.2
+}
+
+
+fn default_inline_links() -> usize {
+    100
+}
+
+fn default_bucket_size() -> usize {
+    500
+}
+
+fn default_max_payload_numbers() -> usize {
+    10_000
+}
+
+fn default_min_similarity() -> f64 {
+    0.8
+}
+
+fn default_max_block_size() -> usize {
+    200
+}
+
+fn default_max_geo_neighbors() -> usize {
+    50
+}
+
+fn default_ngram_size() -> usize {
+    4
+}
\ No newline at end of file
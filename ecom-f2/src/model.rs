@@ -97,6 +97,23 @@ impl EcomF2Order {
         self.billing_identity.full_name()
     }
 
+    pub fn billing_address(&self) -> Option<&Address> {
+        self.billing_identity.address.as_ref()
+    }
+
+    pub fn shipment_address(&self) -> Option<&Address> {
+        self.deviating_shipment_identity
+            .as_ref()
+            .and_then(|identity| identity.address.as_ref())
+    }
+
+    pub fn payment_method(&self) -> Option<String> {
+        self.billing_identity
+            .payment_details
+            .as_ref()
+            .and_then(|details| details.identifier())
+    }
+
     pub fn customer_phone_numbers(&self) -> Vec<String> {
         self.billing_identity
             .phone_numbers
@@ -233,6 +250,11 @@ pub struct Address {
 }
 
 impl Address {
+    /// `(longitude, latitude)` when the address is geocoded.
+    pub fn coordinates(&self) -> Option<(f64, f64)> {
+        self.longitude.zip(self.latitude)
+    }
+
     pub fn single_line(&self) -> String {
         let mut parts = Vec::new();
         if let Some(street) = self.street.as_ref() {
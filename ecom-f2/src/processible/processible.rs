@@ -1,8 +1,11 @@
 use async_trait::async_trait;
 use mongodb::bson::oid::ObjectId;
 use processing::model::{
-    ConnectedTransaction, DirectConnection, Feature, FeatureValue, MatchingField, Processible,
+    ConnectedTransaction, DirectConnection, Feature, FeatureValue, MatchingField, Neighborhood,
+    Processible,
 };
+use processing::fraud_features::fraud_connection_features;
+use processing::velocity::{Velocity, VelocityWindow};
 
 use crate::model::EcomF2Order;
 
@@ -75,13 +78,21 @@ impl Processible for EcomF2Order {
             value: Box::new(FeatureValue::Bool(total_amount > 1000.0)),
         });
 
+        if let Some(method) = self.payment_method() {
+            features.push(Feature {
+                name: "payment_method".to_string(),
+                value: Box::new(FeatureValue::String(method)),
+            });
+        }
+
         features
     }
 
     fn extract_graph_features(
         &self,
-        connected_transactions: &[ConnectedTransaction<Self::Id>],
-        direct_connections: &[DirectConnection<Self::Id>],
+        connected_transactions: &[ConnectedTransaction],
+        direct_connections: &[DirectConnection],
+        neighborhood: &Neighborhood,
     ) -> Vec<Feature> {
         let mut features = Vec::new();
         // Add connection-related features
@@ -95,6 +106,44 @@ impl Processible for EcomF2Order {
             value: Box::new(FeatureValue::Int(direct_connections.len() as i64)),
         });
 
+        features.extend(fraud_connection_features(
+            connected_transactions,
+            direct_connections,
+            neighborhood.scored_at,
+        ));
+
+        let velocity = Velocity::new(neighborhood, direct_connections);
+        features.extend(velocity.count_features("customer.email", "email_orders"));
+        features.extend(velocity.count_features("deviceIdent.token", "device_orders"));
+        features.push(Feature {
+            name: "email_amount_sum_24h".to_string(),
+            value: Box::new(FeatureValue::Double(velocity.sum(
+                "customer.email",
+                VelocityWindow::DAY,
+                "amount",
+            ))),
+        });
+        features.extend(velocity.distinct_features(
+            "deviceIdent.token",
+            "payment_method",
+            "device_payment_methods",
+        ));
+
+        let geo_distances: Vec<f64> = direct_connections
+            .iter()
+            .filter_map(|connection| connection.distance_km)
+            .collect();
+        features.push(Feature {
+            name: "geo_neighbor_count".to_string(),
+            value: Box::new(FeatureValue::Int(geo_distances.len() as i64)),
+        });
+        if let Some(min_distance) = geo_distances.iter().copied().reduce(f64::min) {
+            features.push(Feature {
+                name: "min_geo_distance_km".to_string(),
+                value: Box::new(FeatureValue::Double(min_distance)),
+            });
+        }
+
         features
     }
 
@@ -121,6 +170,28 @@ impl Processible for EcomF2Order {
             ));
         }
 
+        // Coordinates feed the geo matchers reading these fields
+        let addresses = [
+            ("billing.address", self.billing_address()),
+            ("shipment.address", self.shipment_address()),
+        ];
+        for (matcher, address) in addresses {
+            let Some(address) = address else { continue };
+            let line = address.single_line();
+            if line.is_empty() {
+                continue;
+            }
+            fields.push(match address.coordinates() {
+                Some(location) => MatchingField::new_with_timespace(
+                    matcher.to_string(),
+                    line,
+                    self.created(),
+                    location,
+                ),
+                None => MatchingField::new_simple(matcher.to_string(), line),
+            });
+        }
+
         fields
     }
 }
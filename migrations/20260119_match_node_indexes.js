@@ -1,4 +1,5 @@
 // MongoDB index creation for match_nodes to support graph traversal and filters
+// The same indexes are created on startup by MongoCommonStorage::ensure_indexes.
 // Apply with: mongo <db> migrations/20260119_match_node_indexes.js
 
 db.match_nodes.createIndex({ payload_numbers: 1, confidence: -1 });
@@ -0,0 +1,11 @@
+// Match nodes keep only their most recent links inline and move older ones into
+// `match_node_transactions` buckets; `link_count` tells traversal when buckets have to be read.
+// Backfills it for nodes written before, their links are all still inline.
+// Apply with: mongo <db> migrations/20261016_match_node_link_count.js
+
+db.match_nodes.updateMany(
+  { link_count: { $exists: false } },
+  [{ $set: { link_count: { $toLong: { $size: { $ifNull: ["$transaction_data", []] } } } } }]
+);
+db.match_node_transactions.createIndex({ matcher: 1, value: 1, count: 1 });
+db.match_node_transactions.createIndex({ "transaction_data.payload_number": 1 });
@@ -0,0 +1,111 @@
+// Matching values are normalized per matcher (`processor.matcher_normalizers`) before the node
+// lookup. Nodes written before hold raw values, so new transactions would no longer link to
+// historic ones with the same email. Renormalizes existing nodes, merging the ones that now share
+// a value, and rewrites their buckets and the `matching_fields` of transactions.
+// Stop the processors before applying, and keep NORMALIZERS and the limits below in sync with
+// the deployed config.
+// Apply with: mongo <db> migrations/20261016_normalize_match_node_values.js
+
+var INLINE_LINKS = 100;
+var BUCKET_SIZE = 500;
+var HUB_THRESHOLD = 1000;
+var MAX_PAYLOAD_NUMBERS = 10000;
+
+function trim(value) {
+  return value.split(/\s+/).filter(function (part) { return part.length > 0; }).join(" ");
+}
+
+function lowercase(value) {
+  return value.toLowerCase();
+}
+
+function unicodeFold(value) {
+  return value.normalize("NFKD").replace(/\p{M}/gu, "");
+}
+
+function email(value) {
+  var normalized = value.trim().toLowerCase();
+  var at = normalized.lastIndexOf("@");
+  if (at < 0) return normalized;
+  var local = normalized.slice(0, at);
+  var domain = normalized.slice(at + 1);
+  if (domain === "gmail.com" || domain === "googlemail.com") {
+    return local.split("+")[0].replace(/\./g, "") + "@gmail.com";
+  }
+  return normalized;
+}
+
+var NORMALIZERS = {
+  "customer.email": [email],
+  "customer.name": [unicodeFold, trim, lowercase],
+  "deviceIdent.token": [trim],
+};
+
+function normalize(matcher, value) {
+  return NORMALIZERS[matcher].reduce(function (current, normalizer) { return normalizer(current); }, value);
+}
+
+function linkTime(link) {
+  // Naive timestamps, read as UTC like the processor does
+  return Date.parse(String(link.created_at).slice(0, 23) + "Z");
+}
+
+function mergeInto(target, source) {
+  var payloadNumbers = target.payload_numbers || [];
+  (source.payload_numbers || []).forEach(function (pn) {
+    if (payloadNumbers.indexOf(pn) === -1) payloadNumbers.push(pn);
+  });
+  var capped = !!target.payload_numbers_capped || !!source.payload_numbers_capped
+    || payloadNumbers.length > MAX_PAYLOAD_NUMBERS;
+  payloadNumbers = payloadNumbers.slice(0, MAX_PAYLOAD_NUMBERS);
+
+  var links = (target.transaction_data || []).concat(source.transaction_data || []);
+  links.sort(function (a, b) { return linkTime(a) - linkTime(b); });
+  var evicted = links.slice(0, Math.max(links.length - INLINE_LINKS, 0));
+  var inline = links.slice(evicted.length);
+  for (var i = 0; i < evicted.length; i += BUCKET_SIZE) {
+    var chunk = evicted.slice(i, i + BUCKET_SIZE);
+    db.match_node_transactions.insertOne({ matcher: target.matcher, value: target.value, transaction_data: chunk, count: NumberLong(chunk.length) });
+  }
+
+  var hubOverride = target.hub_override !== undefined && target.hub_override !== null ? target.hub_override : source.hub_override;
+  db.match_nodes.updateOne(
+    { _id: target._id },
+    { $set: {
+      payload_numbers: payloadNumbers,
+      payload_numbers_capped: capped,
+      transaction_data: inline,
+      link_count: NumberLong((target.link_count || 0) + (source.link_count || 0)),
+      cardinality: NumberLong(payloadNumbers.length),
+      is_hub: capped || payloadNumbers.length >= HUB_THRESHOLD,
+      hub_override: hubOverride === undefined ? null : hubOverride,
+    } }
+  );
+  db.match_nodes.deleteOne({ _id: source._id });
+}
+
+Object.keys(NORMALIZERS).forEach(function (matcher) {
+  var renamed = 0;
+  var merged = 0;
+  db.match_nodes.find({ matcher: matcher }, { _id: 1, value: 1 }).toArray().forEach(function (node) {
+    var value = normalize(matcher, node.value);
+    if (value === node.value) return;
+
+    var source = db.match_nodes.findOne({ _id: node._id });
+    var target = db.match_nodes.findOne({ matcher: matcher, value: value });
+    if (target) {
+      mergeInto(target, source);
+      merged++;
+    } else {
+      db.match_nodes.updateOne({ _id: node._id }, { $set: { value: value } });
+      renamed++;
+    }
+    db.match_node_transactions.updateMany({ matcher: matcher, value: node.value }, { $set: { value: value } });
+    db.transactions.updateMany(
+      { matching_fields: { $elemMatch: { matcher: matcher, value: node.value } } },
+      { $set: { "matching_fields.$[field].value": value } },
+      { arrayFilters: [{ "field.matcher": matcher, "field.value": node.value }] }
+    );
+  });
+  print(matcher + ": " + renamed + " nodes renamed, " + merged + " merged");
+});
@@ -0,0 +1,15 @@
+// Match nodes used to be written to `match_node` while $graphLookup and the indexes use `match_nodes`.
+// Moves existing nodes into `match_nodes` so multi-hop traversal sees them.
+// Apply with: mongo <db> migrations/20261016_rename_match_node_collection.js
+
+if (db.getCollectionNames().includes("match_node")) {
+  if (db.match_nodes.countDocuments({}) === 0) {
+    db.match_nodes.drop();
+    db.match_node.renameCollection("match_nodes");
+  } else {
+    db.match_node.find().forEach(function (node) {
+      db.match_nodes.replaceOne({ _id: node._id }, node, { upsert: true });
+    });
+    db.match_node.drop();
+  }
+}
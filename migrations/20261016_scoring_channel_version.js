@@ -0,0 +1,8 @@
+// Scoring channels carry a `version` that is recorded on their scoring events as
+// `channel_version`. Channels stored before start at 1; edits must increment it, e.g.
+// db.model_activations.updateOne({ _id: id }, { $set: { ... }, $inc: { version: 1 } }).
+// Running processors keep scoring a channel edited without a new version with its previous
+// definition.
+// Apply with: mongo <db> migrations/20261016_scoring_channel_version.js
+
+db.model_activations.updateMany({ version: { $exists: false } }, { $set: { version: 1 } });
@@ -0,0 +1,26 @@
+// Scoring events store their total score and per-rule contributions. Backfills both for
+// events written before from the expression rules of their channel; the decision stays
+// unset since thresholds did not exist when those events were scored.
+// Apply with: mongo <db> migrations/20261016_scoring_event_scores.js
+
+db.model_activations.find({}).forEach(function (channel) {
+  var scores = {};
+  (channel.model.expression_rules || []).forEach(function (rule) {
+    scores[rule.name] = rule.score;
+  });
+
+  db.scoring_events
+    .find({ channel_id: channel._id, total_score: { $exists: false } })
+    .forEach(function (event) {
+      var ruleScores = event.triggered_rules
+        .filter(function (name) { return scores[name] !== undefined; })
+        .map(function (name) { return { name: name, score: scores[name] }; });
+      var total = ruleScores.reduce(function (sum, rule) { return sum + rule.score; }, 0);
+      db.scoring_events.updateOne(
+        { _id: event._id },
+        { $set: { total_score: total, rule_scores: ruleScores, decision: null } }
+      );
+    });
+});
+db.scoring_events.createIndex({ transaction_id: 1 });
+db.scoring_events.createIndex({ decision: 1, created_at: -1 });
@@ -0,0 +1,17 @@
+// Scoring models keep what their scorer needs in a `payload` tagged by `kind` instead of
+// `model_type` next to `expression_rules` and `artifact_path`. Moves models stored before.
+// Apply with: mongo <db> migrations/20261016_scoring_model_payload.js
+
+db.model_activations.find({ "model.payload": { $exists: false } }).forEach(function (channel) {
+  var model = channel.model;
+  var payload = model.model_type === "MachineLearning"
+    ? { kind: "machine_learning", artifact_path: model.artifact_path }
+    : { kind: "expression_based", expression_rules: model.expression_rules || [] };
+  db.model_activations.updateOne(
+    { _id: channel._id },
+    {
+      $set: { "model.payload": payload },
+      $unset: { "model.model_type": "", "model.expression_rules": "", "model.artifact_path": "" }
+    }
+  );
+});
@@ -0,0 +1,5 @@
+// Shadow scoring channels store their events in `shadow_scoring_events`, read by the channel
+// comparison report. The same index is created on startup by MongoCommonStorage::ensure_indexes.
+// Apply with: mongo <db> migrations/20261016_shadow_scoring_events.js
+
+db.shadow_scoring_events.createIndex({ channel_id: 1, created_at: -1 });
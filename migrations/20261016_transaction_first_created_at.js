@@ -0,0 +1,20 @@
+// Velocity windows order a transaction and its neighbors by `first_created_at`, the creation time
+// of the first version carried over on re-import. Sets it on every version of payloads with versions
+// stored before, from the lowest stored version, including versions re-imported since deployment
+// that carried the creation time of their predecessor.
+// Apply with: mongo <db> migrations/20261016_transaction_first_created_at.js
+
+db.transactions.aggregate([
+  { $match: { first_created_at: { $exists: false } } },
+  { $group: { _id: "$payload_number" } },
+]).forEach(function (group) {
+  var first = db.transactions
+    .find({ payload_number: group._id }, { created_at: 1 })
+    .sort({ transaction_version: 1 })
+    .limit(1)
+    .next();
+  db.transactions.updateMany(
+    { payload_number: group._id },
+    { $set: { first_created_at: first.created_at } }
+  );
+});
@@ -0,0 +1,30 @@
+// Match nodes are upserted on (matcher, value), which needs the pair to be unique.
+// Merges nodes duplicated by concurrent writers, then replaces the plain index with a unique one.
+// Apply with: mongo <db> migrations/20261016_unique_match_node_values.js
+
+db.match_nodes.aggregate([
+  { $group: { _id: { matcher: "$matcher", value: "$value" }, ids: { $push: "$_id" }, count: { $sum: 1 } } },
+  { $match: { count: { $gt: 1 } } },
+], { allowDiskUse: true }).forEach(function (group) {
+  var nodes = db.match_nodes.find({ _id: { $in: group.ids } }).sort({ _id: 1 }).toArray();
+  var keep = nodes[0];
+  var transactionData = [];
+  var payloadNumbers = [];
+  nodes.forEach(function (node) {
+    transactionData = transactionData.concat(node.transaction_data || []);
+    (node.payload_numbers || []).forEach(function (pn) {
+      if (payloadNumbers.indexOf(pn) === -1) payloadNumbers.push(pn);
+    });
+  });
+  db.match_nodes.updateOne(
+    { _id: keep._id },
+    { $set: { transaction_data: transactionData, payload_numbers: payloadNumbers, cardinality: payloadNumbers.length } }
+  );
+  db.match_nodes.deleteMany({ _id: { $in: group.ids.filter(function (id) { return !id.equals(keep._id); }) } });
+});
+
+if (db.match_nodes.getIndexes().some(function (index) { return index.name === "matcher_1_value_1" && !index.unique; })) {
+  db.match_nodes.dropIndex("matcher_1_value_1");
+}
+db.match_nodes.createIndex({ matcher: 1, value: 1 }, { unique: true });
+db.match_nodes.createIndex({ is_hub: 1, cardinality: -1 });
@@ -0,0 +1,91 @@
+use thiserror::Error as ThisError;
+
+type Source = Box<dyn std::error::Error + Send + Sync>;
+
+/// Errors of the processing pipeline. Variants are split by what a caller can do about them:
+/// storage and queue failures are usually transient and worth retrying, while bad payloads or
+/// missing configuration fail the same way on every attempt.
+#[derive(Debug, ThisError)]
+pub enum Error {
+    #[error("deserialization failed: {0}")]
+    Deserialization(#[source] Source),
+    #[error("serialization failed: {0}")]
+    Serialization(#[source] Source),
+    #[error("matcher config not found: {0}")]
+    MissingMatcherConfig(String),
+    #[error("not found: {0}")]
+    NotFound(String),
+    #[error("feature validation failed: {0}")]
+    InvalidFeatures(String),
+    #[error("storage error: {0}")]
+    Storage(#[source] Source),
+    #[error("queue error: {0}")]
+    Queue(#[source] Source),
+    #[error("scoring error: {0}")]
+    Scoring(String),
+    #[error("panicked: {0}")]
+    Panic(String),
+    #[error("migration required: {0}")]
+    PendingMigration(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    /// Whether another attempt on the same message can succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Error::Storage(_) | Error::Queue(_))
+    }
+}
+
+impl From<mongodb::error::Error> for Error {
+    fn from(e: mongodb::error::Error) -> Self {
+        Error::Storage(Box::new(e))
+    }
+}
+
+impl From<mongodb::bson::ser::Error> for Error {
+    fn from(e: mongodb::bson::ser::Error) -> Self {
+        Error::Serialization(Box::new(e))
+    }
+}
+
+impl From<mongodb::bson::de::Error> for Error {
+    fn from(e: mongodb::bson::de::Error) -> Self {
+        Error::Deserialization(Box::new(e))
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Serialization(Box::new(e))
+    }
+}
+
+impl From<pgmq::PgmqError> for Error {
+    fn from(e: pgmq::PgmqError) -> Self {
+        Error::Queue(Box::new(e))
+    }
+}
+
+impl From<sea_orm::DbErr> for Error {
+    fn from(e: sea_orm::DbErr) -> Self {
+        Error::Queue(Box::new(e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_infrastructure_errors_are_retryable() {
+        assert!(Error::Storage("connection reset".into()).is_retryable());
+        assert!(Error::Queue("pool timed out".into()).is_retryable());
+        assert!(!Error::Deserialization("missing field".into()).is_retryable());
+        assert!(!Error::MissingMatcherConfig("customer.email".to_string()).is_retryable());
+        assert!(!Error::Scoring("unsupported model".to_string()).is_retryable());
+        assert!(!Error::Panic("boom".to_string()).is_retryable());
+        assert!(!Error::PendingMigration("unique index".to_string()).is_retryable());
+    }
+}
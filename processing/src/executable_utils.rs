@@ -1,9 +1,13 @@
 use crate::{
+    fuzzy::FuzzyMatcherRegistry,
+    geo::GeoMatcherRegistry,
+    graph_export::{to_cytoscape, to_graphml},
     importer::Importer,
+    labeling::Labeler,
     model::{FraudLevel, LabelSource, Processible, ProcessibleSerde},
     processor::Processor,
-    queue::{ProdQueue, QueueName},
-    scorers::{ExpressionBasedScorer, Scorer},
+    queue::{DeadLetterService, ProdQueue, QueueName, QueueService, retry_backoff_seconds},
+    scorers::{ScorerFactory, ScorerSet},
     storage::{CommonStorage, mongo_common::MongoCommonStorage},
 };
 use async_graphql::http::GraphiQLSource;
@@ -15,15 +19,22 @@ use axum::{
     response::{self, IntoResponse, Response},
     routing::{get, post},
 };
-use metrics::gauge;
+use metrics::{Gauge, gauge};
 
 use clap::Parser;
-use common::config::Config;
+use common::config::{Config, ProcessorConfig};
 use http::header;
 use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
 use mongodb::bson::oid::ObjectId;
 use once_cell::sync::OnceCell;
-use std::{error::Error, fmt::Debug, marker::PhantomData, sync::Arc};
+use std::{
+    collections::HashMap, error::Error, fmt::Debug, marker::PhantomData, sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::watch,
+    task::{self, JoinSet},
+};
 use tower_http::{
     cors::{Any, CorsLayer},
     trace::TraceLayer,
@@ -108,12 +119,7 @@ pub async fn run_processor<P: Processible + ProcessibleSerde<Id = ObjectId>>(con
 
 
     let active_channels = common_storage.get_active_model_activations().await?;
-    let scorers: Vec<Arc<dyn Scorer>> = active_channels
-        .iter()
-        .map(|channel| 
-            Arc::new(ExpressionBasedScorer::new(channel.clone())) as Arc<dyn Scorer>
-        )
-        .collect();
+    let scorers = ScorerSet::load(ScorerFactory::default(), &active_channels).await?;
 
 
     let processor =
@@ -123,27 +129,168 @@ pub async fn run_processor<P: Processible + ProcessibleSerde<Id = ObjectId>>(con
             scorers,
         ).await?);
 
-    let mut set = tokio::task::JoinSet::new();
-    for _ in 0..config.processor.threads {
-        set.spawn(processor.clone().start_processing_worker());
+    run_supervised_workers(processor, &config.processor).await;
+
+    Ok(())
+}
+
+struct WorkerState {
+    worker_id: u32,
+    failures: i32,
+    started_at: Instant,
+}
+
+/// Decrements the live workers gauge when a worker future completes, panics or is dropped.
+struct LiveWorkerGuard(Gauge);
+
+impl LiveWorkerGuard {
+    fn start() -> Self {
+        let gauge = gauge!("frida_processor_live_workers");
+        gauge.increment(1.0);
+        Self(gauge)
+    }
+}
+
+impl Drop for LiveWorkerGuard {
+    fn drop(&mut self) {
+        self.0.decrement(1.0);
+    }
+}
+
+fn spawn_worker<P: Processible + ProcessibleSerde<Id = ObjectId>>(
+    set: &mut JoinSet<crate::error::Result<()>>,
+    workers: &mut HashMap<task::Id, WorkerState>,
+    processor: Arc<Processor<P>>,
+    config: &ProcessorConfig,
+    worker_id: u32,
+    failures: i32,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let restart_delay = if failures > 0 {
+        retry_backoff_seconds(
+            failures,
+            config.worker_restart_backoff_base_seconds,
+            config.worker_restart_backoff_max_seconds,
+        )
+    } else {
+        0
+    };
+
+    let handle = set.spawn(async move {
+        if restart_delay > 0 {
+            info!("Restarting worker {} in {}s", worker_id, restart_delay);
+            tokio::select! {
+                _ = tokio::time::sleep(Duration::from_secs(restart_delay as u64)) => {}
+                _ = shutdown.changed() => return Ok(()),
+            }
+        }
+        let _live = LiveWorkerGuard::start();
+        processor.start_processing_worker(shutdown).await
+    });
+
+    workers.insert(
+        handle.id(),
+        WorkerState {
+            worker_id,
+            failures,
+            started_at: Instant::now(),
+        },
+    );
+}
+
+/// Keeps `config.threads` workers alive, restarting failed ones with exponential backoff.
+/// On SIGTERM/Ctrl-C workers are told to stop and in-flight batches are awaited before returning.
+pub async fn run_supervised_workers<P: Processible + ProcessibleSerde<Id = ObjectId>>(
+    processor: Arc<Processor<P>>,
+    config: &ProcessorConfig,
+) {
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let mut set = JoinSet::new();
+    let mut workers: HashMap<task::Id, WorkerState> = HashMap::new();
+    let reconciliation = tokio::spawn(processor.clone().start_reconciliation_worker(shutdown_rx.clone()));
+    let channel_reload = tokio::spawn(processor.clone().start_channel_reload_worker(shutdown_rx.clone()));
+
+    for worker_id in 0..config.threads {
+        spawn_worker(&mut set, &mut workers, processor.clone(), config, worker_id, 0, shutdown_rx.clone());
     }
     info!("computation started");
 
+    let mut shutdown = std::pin::pin!(shutdown_signal());
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => {
+                info!("Shutdown requested, finishing in-flight messages");
+                let _ = shutdown_tx.send(true);
+                break;
+            }
+            joined = set.join_next_with_id() => {
+                let Some(joined) = joined else { break };
+                let (task_id, result) = match joined {
+                    Ok((task_id, result)) => (task_id, result.map_err(|e| e.to_string())),
+                    Err(e) => (e.id(), Err(format!("join error: {:?}", e))),
+                };
+                let Some(state) = workers.remove(&task_id) else { continue };
+
+                let failures = match result {
+                    Ok(()) => {
+                        info!("worker {} finished ok", state.worker_id);
+                        0
+                    }
+                    Err(e) => {
+                        error!("worker {} finished with error: {}", state.worker_id, e);
+                        // A worker that survived longer than the max backoff starts over
+                        let healthy_for = Duration::from_secs(config.worker_restart_backoff_max_seconds.max(0) as u64);
+                        if state.started_at.elapsed() > healthy_for { 1 } else { state.failures + 1 }
+                    }
+                };
+                spawn_worker(&mut set, &mut workers, processor.clone(), config, state.worker_id, failures, shutdown_rx.clone());
+            }
+        }
+    }
+
     while let Some(join_res) = set.join_next().await {
         match join_res {
-            Ok(Ok(())) => {
-                info!("computation finished ok");
-            }
-            Ok(Err(e)) => {
-                error!("computation finished with error: {:?}", e);
+            Ok(Ok(())) => info!("computation finished ok"),
+            Ok(Err(e)) => error!("computation finished with error: {:?}", e),
+            Err(e) => error!("computation finished with join error: {:?}", e),
+        }
+    }
+    if let Err(e) = reconciliation.await {
+        error!("reconciliation finished with join error: {:?}", e);
+    }
+    if let Err(e) = channel_reload.await {
+        error!("channel reload finished with join error: {:?}", e);
+    }
+    info!("All workers stopped");
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
             }
             Err(e) => {
-                error!("computation finished with join error: {:?}", e);
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
             }
         }
-    }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-    Ok(())
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
 }
 
 #[derive(Parser, Debug)]
@@ -280,14 +427,37 @@ where
     P: Processible + ProcessibleSerde<Id = ObjectId> + Send + Sync + Clone + 'static,
 {
     initialize_tracing(&config.backend.log_level);
-    let common_storage: Arc<dyn CommonStorage<P::Id>> =
-        Arc::new(MongoCommonStorage::new(&config.common.database_url, "frida").await?);
+    // Label propagation traverses the graph like the processor does
+    let common_storage: Arc<dyn CommonStorage<P::Id>> = Arc::new(
+        MongoCommonStorage::new(&config.common.database_url, "frida")
+            .await?
+            .with_hub_config(config.processor.hubs.clone())
+            .with_match_node_storage_config(config.processor.match_node_storage.clone())
+            .with_fuzzy_matchers(FuzzyMatcherRegistry::from_config(&config.processor.fuzzy_matchers))
+            .with_geo_matchers(GeoMatcherRegistry::from_config(&config.processor.geo_matchers)),
+    );
 
-    let schema = crate::storage::graphql_schema::schema::<P>(common_storage.clone()).unwrap();
+    let dead_letter_queue: Arc<dyn DeadLetterService<P::Id>> =
+        Arc::new(ProdQueue::new(&config.common, QueueName::DeadLetter).await?);
+    let recalc_queue: Arc<dyn QueueService<P::Id>> =
+        Arc::new(ProdQueue::new(&config.common, QueueName::Recalculation).await?);
+    let labeler = Arc::new(Labeler::new(
+        common_storage.clone(),
+        recalc_queue,
+        config.processor.label_traversal.clone(),
+    ));
+
+    let schema = crate::storage::graphql_schema::schema::<P>(
+        common_storage.clone(),
+        dead_letter_queue.clone(),
+    )
+    .unwrap();
 
     let state = AppState {
         _phantom: PhantomData,
         common_storage,
+        dead_letter_queue,
+        labeler,
     };
 
     // init prometheus and capture handle for /metrics
@@ -299,6 +469,9 @@ where
             get(graphiql).post_service(GraphQL::new(schema)),
         )
         .route("/api/transactions/label", post(label_transaction::<P>))
+        .route("/api/transactions/graph", get(export_transaction_graph::<P>))
+        .route("/api/dead_letters", get(list_dead_letters::<P>))
+        .route("/api/dead_letters/requeue", post(requeue_dead_letters::<P>))
         .route("/health", get(health_check))
         .route(
             &metrics_path,
@@ -332,6 +505,8 @@ where
 pub struct AppState<T: Processible + Send + Sync + ProcessibleSerde<Id = ObjectId> + 'static> {
     // web_storage: Arc<dyn WebStorage<T>>,
     common_storage: Arc<dyn CommonStorage<T::Id>>,
+    dead_letter_queue: Arc<dyn DeadLetterService<T::Id>>,
+    labeler: Arc<Labeler<T::Id>>,
     _phantom: PhantomData<T>,
 }
 
@@ -339,10 +514,14 @@ impl<T: Processible + Send + Sync + ProcessibleSerde<Id = ObjectId> + 'static> A
     pub fn new(
         // web_storage: Arc<dyn WebStorage<T>>,
         common_storage: Arc<dyn CommonStorage<T::Id>>,
+        dead_letter_queue: Arc<dyn DeadLetterService<T::Id>>,
+        labeler: Arc<Labeler<T::Id>>,
     ) -> Self {
         Self {
             // web_storage,
             common_storage,
+            dead_letter_queue,
+            labeler,
             _phantom: PhantomData,
         }
     }
@@ -368,10 +547,9 @@ pub async fn label_transaction<P: Processible + Send + Sync + ProcessibleSerde<I
         label_request.payload_numbers.len()
     );
 
-    // Use the new business logic method
     match state
-        .common_storage
-        .label_transactions(
+        .labeler
+        .label(
             &label_request.payload_numbers,
             &label_request.fraud_level,
             &label_request.fraud_category,
@@ -380,14 +558,15 @@ pub async fn label_transaction<P: Processible + Send + Sync + ProcessibleSerde<I
         )
         .await
     {
-        Ok(transaction_ids) => {
+        Ok(recalculated) => {
             tracing::info!(
-                "Successfully labeled {:?} {:?} transactions: {:?}",
+                "Successfully labeled {:?} {:?} transactions: {:?}, {} neighbors queued for recalculation",
                 label_request.fraud_level,
                 &label_request.fraud_category,
-                transaction_ids
+                label_request.payload_numbers,
+                recalculated
             );
-            StatusCode::OK.into_response()
+            (StatusCode::OK, Json(serde_json::json!({ "recalculated": recalculated }))).into_response()
         }
         Err(e) => {
             tracing::error!(
@@ -399,3 +578,85 @@ pub async fn label_transaction<P: Processible + Send + Sync + ProcessibleSerde<I
         }
     }
 }
+
+#[derive(serde::Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphExportFormat {
+    #[default]
+    Graphml,
+    Cytoscape,
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct GraphExportQuery {
+    pub payload_number: String,
+    pub depth: Option<i32>,
+    pub min_confidence: Option<i32>,
+    #[serde(default)]
+    pub format: GraphExportFormat,
+}
+
+/// The connected graph of a payload as a file for external graph viewers.
+pub async fn export_transaction_graph<P: Processible + Send + Sync + ProcessibleSerde<Id = ObjectId>>(
+    axum::extract::State(state): axum::extract::State<AppState<P>>,
+    axum::extract::Query(query): axum::extract::Query<GraphExportQuery>,
+) -> Response {
+    let traversal = crate::graphql::graph_traversal(query.depth.map(i64::from), query.min_confidence.map(i64::from));
+    match state.common_storage.get_transaction_graph(&query.payload_number, &traversal).await {
+        Ok(graph) => match query.format {
+            GraphExportFormat::Graphml => (
+                StatusCode::OK,
+                [(header::CONTENT_TYPE, "application/graphml+xml")],
+                to_graphml(&graph),
+            )
+                .into_response(),
+            GraphExportFormat::Cytoscape => (StatusCode::OK, Json(to_cytoscape(&graph))).into_response(),
+        },
+        Err(e) => {
+            tracing::error!(error = %e, payload_number = %query.payload_number, "Failed to export transaction graph");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct DeadLettersQuery {
+    pub limit: Option<i64>,
+}
+
+pub async fn list_dead_letters<P: Processible + Send + Sync + ProcessibleSerde<Id = ObjectId>>(
+    axum::extract::State(state): axum::extract::State<AppState<P>>,
+    axum::extract::Query(query): axum::extract::Query<DeadLettersQuery>,
+) -> Response {
+    let limit = query.limit.unwrap_or(crate::graphql::DEFAULT_DEAD_LETTERS_LIMIT);
+    match state.dead_letter_queue.list_dead_letters(limit).await {
+        Ok(letters) => (StatusCode::OK, Json(letters)).into_response(),
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to list dead letters");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+#[derive(serde::Deserialize, Debug)]
+pub struct RequeueRequest {
+    pub msg_ids: Vec<i64>,
+}
+
+pub async fn requeue_dead_letters<P: Processible + Send + Sync + ProcessibleSerde<Id = ObjectId>>(
+    axum::extract::State(state): axum::extract::State<AppState<P>>,
+    Json(requeue_request): Json<RequeueRequest>,
+) -> Response {
+    tracing::info!(msg_ids = ?requeue_request.msg_ids, "Requeueing dead letters");
+
+    match state.dead_letter_queue.requeue(&requeue_request.msg_ids).await {
+        Ok(requeued) => {
+            tracing::info!("Requeued {} dead letters", requeued);
+            (StatusCode::OK, Json(serde_json::json!({ "requeued": requeued }))).into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Failed to requeue dead letters");
+            (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::NaiveDateTime;
+
+use crate::model::{ConnectedTransaction, DirectConnection, Feature, FeatureValue, mongo_model::Label};
+
+/// Depths of fraud neighbor counts, the last one also counts everything further away.
+pub const FRAUD_DEPTHS: usize = 3;
+
+fn is_fraud(label: &Option<Label>) -> bool {
+    label.as_ref().is_some_and(|label| label.fraud_level.is_fraud())
+}
+
+/// Features about fraud-labeled neighbors, computed from connections labeled by
+/// `Neighborhood::label_connections`. `scored_at` is when the features are computed, so labels
+/// set after the transaction was created keep aging on recalculation.
+///
+/// - `fraud_neighbors_depth_<n>`: connected transactions labeled as fraud `n` hops away
+/// - `fraud_direct_count`: directly connected transactions labeled as fraud
+/// - `fraud_max_confidence`: highest path or direct confidence to a fraud, 0 without one
+/// - `fraud_label_age_hours`: hours from the most recent fraud label to `scored_at`, only set
+///   when a neighbor is labeled as fraud
+pub fn fraud_connection_features(
+    connected_transactions: &[ConnectedTransaction],
+    direct_connections: &[DirectConnection],
+    scored_at: NaiveDateTime,
+) -> Vec<Feature> {
+    let fraud_connected: Vec<&ConnectedTransaction> =
+        connected_transactions.iter().filter(|ct| is_fraud(&ct.label)).collect();
+    let fraud_direct: Vec<&DirectConnection> =
+        direct_connections.iter().filter(|dc| is_fraud(&dc.label)).collect();
+
+    let mut by_depth = [0i64; FRAUD_DEPTHS];
+    for ct in &fraud_connected {
+        let depth = ct.path.len().clamp(1, FRAUD_DEPTHS);
+        by_depth[depth - 1] += 1;
+    }
+    let mut features: Vec<Feature> = by_depth
+        .iter()
+        .enumerate()
+        .map(|(i, count)| Feature {
+            name: format!("fraud_neighbors_depth_{}", i + 1),
+            value: Box::new(FeatureValue::Int(*count)),
+        })
+        .collect();
+
+    features.push(Feature {
+        name: "fraud_direct_count".to_string(),
+        value: Box::new(FeatureValue::Int(fraud_direct.len() as i64)),
+    });
+
+    let max_confidence = fraud_connected
+        .iter()
+        .map(|ct| ct.total_confidence)
+        .chain(fraud_direct.iter().map(|dc| dc.confidence))
+        .max()
+        .unwrap_or(0);
+    features.push(Feature {
+        name: "fraud_max_confidence".to_string(),
+        value: Box::new(FeatureValue::Int(max_confidence as i64)),
+    });
+
+    let latest_label = fraud_connected
+        .iter()
+        .filter_map(|ct| ct.label.as_ref())
+        .chain(fraud_direct.iter().filter_map(|dc| dc.label.as_ref()))
+        .map(|label| label.created_at)
+        .max();
+    if let Some(labeled_at) = latest_label {
+        // Clock skew between labeling and scoring hosts must not yield negative ages
+        let seconds = (scored_at - labeled_at).num_seconds().max(0);
+        features.push(Feature {
+            name: "fraud_label_age_hours".to_string(),
+            value: Box::new(FeatureValue::Double(seconds as f64 / 3600.0)),
+        });
+    }
+
+    features
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::model::{FraudLevel, LabelSource};
+
+    fn now() -> NaiveDateTime {
+        chrono::DateTime::from_timestamp(1_760_000_000, 0).unwrap().naive_utc()
+    }
+
+    fn label(fraud_level: FraudLevel, hours_ago: i64) -> Option<Label> {
+        Some(Label {
+            fraud_level,
+            fraud_category: "test".to_string(),
+            label_source: LabelSource::Manual,
+            labeled_by: "analyst".to_string(),
+            created_at: now() - Duration::hours(hours_ago),
+        })
+    }
+
+    fn connected(payload_number: &str, depth: usize, total_confidence: i32, label: Option<Label>) -> ConnectedTransaction {
+        ConnectedTransaction {
+            payload_number: payload_number.to_string(),
+            path: vec!["customer.email".to_string(); depth],
+            total_confidence,
+            label,
+        }
+    }
+
+    fn value(features: &[Feature], name: &str) -> Option<FeatureValue> {
+        features.iter().find(|f| f.name == name).map(|f| *f.value.clone())
+    }
+
+    #[test]
+    fn counts_fraud_neighbors_by_depth_with_confidence_and_label_age() {
+        let connected_transactions = vec![
+            connected("A", 1, 90, label(FraudLevel::Fraud, 10)),
+            connected("B", 2, 70, label(FraudLevel::AccountTakeover, 5)),
+            connected("C", 4, 40, label(FraudLevel::Fraud, 50)),
+            connected("D", 1, 100, label(FraudLevel::NoFraud, 1)),
+            connected("E", 1, 100, None),
+        ];
+        let direct_connections = vec![DirectConnection {
+            payload_number: "A".to_string(),
+            matcher: "customer.email".to_string(),
+            confidence: 95,
+            importance: 90,
+            distance_km: None,
+            label: label(FraudLevel::Fraud, 10),
+        }];
+
+        let features = fraud_connection_features(&connected_transactions, &direct_connections, now());
+
+        assert_eq!(value(&features, "fraud_neighbors_depth_1"), Some(FeatureValue::Int(1)));
+        assert_eq!(value(&features, "fraud_neighbors_depth_2"), Some(FeatureValue::Int(1)));
+        assert_eq!(value(&features, "fraud_neighbors_depth_3"), Some(FeatureValue::Int(1)));
+        assert_eq!(value(&features, "fraud_direct_count"), Some(FeatureValue::Int(1)));
+        assert_eq!(value(&features, "fraud_max_confidence"), Some(FeatureValue::Int(95)));
+        assert_eq!(value(&features, "fraud_label_age_hours"), Some(FeatureValue::Double(5.0)));
+    }
+
+    #[test]
+    fn clean_neighborhoods_have_no_label_age() {
+        let connected_transactions = vec![connected("D", 1, 100, label(FraudLevel::NoFraud, 1))];
+
+        let features = fraud_connection_features(&connected_transactions, &[], now());
+
+        assert_eq!(value(&features, "fraud_neighbors_depth_1"), Some(FeatureValue::Int(0)));
+        assert_eq!(value(&features, "fraud_max_confidence"), Some(FeatureValue::Int(0)));
+        assert_eq!(value(&features, "fraud_label_age_hours"), None);
+    }
+
+    #[test]
+    fn label_age_grows_with_the_scoring_time() {
+        let connected_transactions = vec![connected("A", 1, 90, label(FraudLevel::Fraud, 0))];
+
+        let features = fraud_connection_features(&connected_transactions, &[], now() + Duration::hours(30));
+
+        assert_eq!(value(&features, "fraud_label_age_hours"), Some(FeatureValue::Double(30.0)));
+    }
+}
@@ -0,0 +1,209 @@
+//! A reduced Double Metaphone: the common English and German spelling rules, without the
+//! Slavic, Romance and Asian special cases of the full algorithm. Good enough for blocking keys,
+//! candidates are compared by string similarity afterwards anyway.
+
+const MAX_LENGTH: usize = 4;
+
+#[derive(Default)]
+struct Codes {
+    primary: String,
+    alternate: String,
+}
+
+impl Codes {
+    fn add(&mut self, primary: &str, alternate: &str) {
+        self.primary.push_str(primary);
+        self.alternate.push_str(alternate);
+    }
+
+    fn add_both(&mut self, code: &str) {
+        self.add(code, code);
+    }
+
+    fn is_complete(&self) -> bool {
+        self.primary.len() >= MAX_LENGTH && self.alternate.len() >= MAX_LENGTH
+    }
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c, 'A' | 'E' | 'I' | 'O' | 'U' | 'Y')
+}
+
+/// Primary and alternate code of a single word, at most four characters each.
+pub fn double_metaphone(word: &str) -> (String, String) {
+    let chars: Vec<char> = word
+        .chars()
+        .filter(char::is_ascii_alphabetic)
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    let at = |i: usize| chars.get(i).copied().unwrap_or('\0');
+    let starts_with = |i: usize, prefix: &str| {
+        prefix.chars().enumerate().all(|(offset, c)| chars.get(i + offset) == Some(&c))
+    };
+    // Skips a doubled letter, "MM" is coded like "M"
+    let step = |i: usize, c: char| if at(i + 1) == c { 2 } else { 1 };
+
+    let mut codes = Codes::default();
+    let mut i = 0;
+    if ["GN", "KN", "PN", "WR", "PS"].iter().any(|prefix| starts_with(0, prefix)) {
+        i = 1;
+    }
+    if at(0) == 'X' {
+        codes.add_both("S");
+        i = 1;
+    }
+
+    while i < chars.len() && !codes.is_complete() {
+        let c = chars[i];
+        let next = at(i + 1);
+        i += match c {
+            _ if is_vowel(c) => {
+                if i == 0 {
+                    codes.add_both("A");
+                }
+                1
+            }
+            'B' => {
+                codes.add_both("P");
+                step(i, 'B')
+            }
+            'C' => {
+                if starts_with(i, "CH") {
+                    if i == 0 && matches!(at(2), 'R' | 'L') {
+                        codes.add_both("K");
+                    } else {
+                        codes.add("X", "K");
+                    }
+                    2
+                } else if matches!(next, 'I' | 'E' | 'Y') {
+                    codes.add_both("S");
+                    2
+                } else {
+                    codes.add_both("K");
+                    if matches!(next, 'K' | 'Q' | 'C') { 2 } else { 1 }
+                }
+            }
+            'D' => {
+                if next == 'G' && matches!(at(i + 2), 'E' | 'I' | 'Y') {
+                    codes.add_both("J");
+                    3
+                } else {
+                    codes.add_both("T");
+                    if matches!(next, 'T' | 'D') { 2 } else { 1 }
+                }
+            }
+            'G' => match next {
+                'H' => {
+                    // Silent after a vowel, as in "Knight" or "Hugh"
+                    if i == 0 || !is_vowel(at(i - 1)) {
+                        codes.add_both("K");
+                    }
+                    2
+                }
+                'N' => {
+                    codes.add_both("N");
+                    2
+                }
+                'E' | 'I' | 'Y' => {
+                    codes.add("J", "K");
+                    2
+                }
+                _ => {
+                    codes.add_both("K");
+                    step(i, 'G')
+                }
+            },
+            'H' if (i == 0 || is_vowel(at(i - 1))) && is_vowel(next) => {
+                codes.add_both("H");
+                2
+            }
+            'H' => 1,
+            'J' => {
+                codes.add("J", "H");
+                step(i, 'J')
+            }
+            'P' => {
+                if next == 'H' {
+                    codes.add_both("F");
+                    2
+                } else {
+                    codes.add_both("P");
+                    if matches!(next, 'P' | 'B') { 2 } else { 1 }
+                }
+            }
+            'Q' => {
+                codes.add_both("K");
+                step(i, 'Q')
+            }
+            'S' => {
+                if starts_with(i, "SCH") {
+                    codes.add("X", "SK");
+                    3
+                } else if next == 'H' {
+                    codes.add_both("X");
+                    2
+                } else if starts_with(i, "SIO") || starts_with(i, "SIA") {
+                    codes.add("S", "X");
+                    3
+                } else {
+                    codes.add_both("S");
+                    if matches!(next, 'S' | 'Z') { 2 } else { 1 }
+                }
+            }
+            'T' => {
+                if starts_with(i, "TION") {
+                    codes.add_both("X");
+                    3
+                } else if next == 'H' {
+                    codes.add("0", "T");
+                    2
+                } else {
+                    codes.add_both("T");
+                    if matches!(next, 'T' | 'D') { 2 } else { 1 }
+                }
+            }
+            'V' => {
+                codes.add_both("F");
+                step(i, 'V')
+            }
+            'W' => {
+                if i == 0 && is_vowel(next) {
+                    codes.add("A", "F");
+                }
+                1
+            }
+            'X' => {
+                codes.add_both("KS");
+                if matches!(next, 'C' | 'X') { 2 } else { 1 }
+            }
+            'Z' => {
+                codes.add("S", "TS");
+                step(i, 'Z')
+            }
+            'F' | 'K' | 'L' | 'M' | 'N' | 'R' => {
+                codes.add_both(&c.to_string());
+                step(i, c)
+            }
+            _ => 1,
+        };
+    }
+
+    codes.primary.truncate(MAX_LENGTH);
+    codes.alternate.truncate(MAX_LENGTH);
+    (codes.primary, codes.alternate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spelling_variants_share_a_code() {
+        assert_eq!(double_metaphone("Smith").0, double_metaphone("Smyth").0);
+        assert_eq!(double_metaphone("Schmidt"), double_metaphone("Schmitt"));
+        assert_eq!(double_metaphone("Philip").0, double_metaphone("Filip").0);
+        assert_eq!(double_metaphone("Katherine").1, double_metaphone("Catherine").1);
+        assert_eq!(double_metaphone("Meyer"), double_metaphone("Maier"));
+        assert_ne!(double_metaphone("Smith").0, double_metaphone("Jones").0);
+    }
+}
@@ -0,0 +1,223 @@
+mod metaphone;
+
+use std::collections::{BTreeSet, HashMap};
+
+use common::config::{BlockingKeyConfig, FuzzyMatcherConfig};
+
+use crate::model::{MatcherConfig, MatchingField};
+
+pub use metaphone::double_metaphone;
+
+/// A matcher deriving blocking keys from the values of its source matcher.
+#[derive(Debug, Clone)]
+pub struct FuzzyMatcher {
+    pub name: String,
+    pub config: FuzzyMatcherConfig,
+}
+
+impl FuzzyMatcher {
+    /// Keys shared by candidate matches, empty when the value yields none.
+    pub fn blocking_keys(&self, value: &str) -> Vec<String> {
+        match &self.config.blocking {
+            BlockingKeyConfig::Phonetic => {
+                // Words are sorted, so "Doe John" and "John Doe" block together
+                let (primary, alternate): (BTreeSet<String>, BTreeSet<String>) = value
+                    .split_whitespace()
+                    .map(double_metaphone)
+                    .filter(|(primary, _)| !primary.is_empty())
+                    .unzip();
+                let keys: BTreeSet<String> = [primary, alternate]
+                    .into_iter()
+                    .map(|codes| codes.into_iter().collect::<Vec<_>>().join(" "))
+                    .filter(|key| !key.is_empty())
+                    .collect();
+                keys.into_iter().collect()
+            }
+            BlockingKeyConfig::Ngram { size } => {
+                let compact: Vec<char> = compact(value).chars().collect();
+                let size = (*size).max(1);
+                if compact.len() <= size {
+                    return (!compact.is_empty()).then(|| compact.iter().collect()).into_iter().collect();
+                }
+                let grams: BTreeSet<String> = compact.windows(size).map(|gram| gram.iter().collect()).collect();
+                grams.into_iter().collect()
+            }
+            BlockingKeyConfig::EmailDomain => value
+                .rsplit_once('@')
+                .map(|(_, domain)| domain.trim().to_lowercase())
+                .filter(|domain| !domain.is_empty())
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Similarity of two source values in `[0, 1]`.
+    pub fn similarity(&self, a: &str, b: &str) -> f64 {
+        match &self.config.blocking {
+            // Compared in the word order the blocking keys use, swapped names stay similar
+            BlockingKeyConfig::Phonetic => strsim::jaro_winkler(&sorted_words(a), &sorted_words(b)),
+            BlockingKeyConfig::Ngram { .. } => strsim::normalized_levenshtein(&compact(a), &compact(b)),
+            BlockingKeyConfig::EmailDomain => {
+                let local = |email: &str| email.rsplit_once('@').map_or(email, |(local, _)| local).to_lowercase();
+                strsim::jaro_winkler(&local(a), &local(b))
+            }
+        }
+    }
+
+    /// Whether a blocking key shared by `cardinality` payload numbers is too common to compare.
+    pub fn is_oversized_block(&self, cardinality: i64) -> bool {
+        cardinality > self.config.max_block_size as i64
+    }
+
+    /// `confidence` scaled by the similarity of the values, `None` below `min_similarity`.
+    pub fn scaled_confidence(&self, confidence: i32, a: &str, b: &str) -> Option<i32> {
+        let similarity = self.similarity(a, b);
+        (similarity >= self.config.min_similarity).then(|| (confidence as f64 * similarity).round() as i32)
+    }
+}
+
+fn sorted_words(value: &str) -> String {
+    let mut words: Vec<String> = value.split_whitespace().map(str::to_lowercase).collect();
+    words.sort();
+    words.join(" ")
+}
+
+fn compact(value: &str) -> String {
+    value.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+/// Fuzzy matchers keyed by the name of the matcher they produce.
+#[derive(Debug, Clone, Default)]
+pub struct FuzzyMatcherRegistry {
+    matchers: HashMap<String, FuzzyMatcher>,
+}
+
+impl FuzzyMatcherRegistry {
+    pub fn from_config(configs: &HashMap<String, FuzzyMatcherConfig>) -> Self {
+        let matchers = configs
+            .iter()
+            .map(|(name, config)| (name.clone(), FuzzyMatcher { name: name.clone(), config: config.clone() }))
+            .collect();
+        Self { matchers }
+    }
+
+    pub fn get(&self, matcher: &str) -> Option<&FuzzyMatcher> {
+        self.matchers.get(matcher)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.matchers.keys().cloned().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.matchers.is_empty()
+    }
+
+    /// Confidence and importance of the produced matchers, for storage.
+    pub fn matcher_configs(&self) -> HashMap<String, MatcherConfig> {
+        self.matchers
+            .iter()
+            .map(|(name, matcher)| (name.clone(), (matcher.config.confidence, matcher.config.importance)))
+            .collect()
+    }
+
+    /// One field per blocking key of every field a fuzzy matcher is declared for. The source
+    /// value is kept as `source_value`, it is what candidates are compared by.
+    pub fn derive_fields(&self, fields: &[MatchingField]) -> Vec<MatchingField> {
+        let mut matchers: Vec<&FuzzyMatcher> = self.matchers.values().collect();
+        matchers.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut derived = Vec::new();
+        for matcher in matchers {
+            for field in fields.iter().filter(|field| field.matcher == matcher.config.source) {
+                derived.extend(matcher.blocking_keys(&field.value).into_iter().map(|key| MatchingField {
+                    matcher: matcher.name.clone(),
+                    value: key,
+                    raw_value: None,
+                    source_value: Some(field.value.clone()),
+                    ..field.clone()
+                }));
+            }
+        }
+        derived
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> FuzzyMatcherRegistry {
+        let config = |source: &str, blocking| FuzzyMatcherConfig {
+            source: source.to_string(),
+            blocking,
+            confidence: 60,
+            importance: 50,
+            min_similarity: 0.8,
+            max_block_size: 2,
+        };
+        let mut configs = HashMap::new();
+        configs.insert("customer.name.phonetic".to_string(), config("customer.name", BlockingKeyConfig::Phonetic));
+        configs.insert("customer.email.domain".to_string(), config("customer.email", BlockingKeyConfig::EmailDomain));
+        configs.insert("billing.address.ngram".to_string(), config("billing.address", BlockingKeyConfig::Ngram { size: 4 }));
+        FuzzyMatcherRegistry::from_config(&configs)
+    }
+
+    #[test]
+    fn spelling_variants_share_blocking_keys() {
+        let registry = registry();
+        let phonetic = registry.get("customer.name.phonetic").unwrap();
+        let keys = phonetic.blocking_keys("john smith");
+        assert!(phonetic.blocking_keys("Smyth Jon").iter().any(|key| keys.contains(key)));
+
+        let ngram = registry.get("billing.address.ngram").unwrap();
+        assert_eq!(ngram.blocking_keys("Main St."), vec!["ains", "inst", "main"]);
+        assert_eq!(ngram.blocking_keys("A1"), vec!["a1"]);
+
+        let domain = registry.get("customer.email.domain").unwrap();
+        assert_eq!(domain.blocking_keys("jane@Example.com"), vec!["example.com"]);
+        assert!(domain.blocking_keys("no-email").is_empty());
+    }
+
+    #[test]
+    fn confidence_is_scaled_by_similarity() {
+        let registry = registry();
+        let phonetic = registry.get("customer.name.phonetic").unwrap();
+        let confidence = phonetic.scaled_confidence(60, "john smith", "jon smith").unwrap();
+        assert!(confidence > 50 && confidence < 60);
+        assert_eq!(phonetic.scaled_confidence(60, "john smith", "john smith"), Some(60));
+        assert_eq!(phonetic.scaled_confidence(60, "john smith", "mary jones"), None);
+    }
+
+    #[test]
+    fn swapped_names_are_as_similar_as_they_block() {
+        let registry = registry();
+        let phonetic = registry.get("customer.name.phonetic").unwrap();
+        assert_eq!(phonetic.blocking_keys("Doe John"), phonetic.blocking_keys("John Doe"));
+        assert_eq!(phonetic.scaled_confidence(60, "Doe John", "John Doe"), Some(60));
+    }
+
+    #[test]
+    fn blocks_above_the_size_limit_are_oversized() {
+        let registry = registry();
+        let domain = registry.get("customer.email.domain").unwrap();
+        assert!(!domain.is_oversized_block(2));
+        assert!(domain.is_oversized_block(3));
+    }
+
+    #[test]
+    fn derived_fields_keep_the_source_value() {
+        let fields = vec![
+            MatchingField::new_simple("customer.name".to_string(), "john smith".to_string()),
+            MatchingField::new_simple("customer.email".to_string(), "john@example.com".to_string()),
+        ];
+        let derived = registry().derive_fields(&fields);
+
+        let domain: Vec<&MatchingField> = derived.iter().filter(|f| f.matcher == "customer.email.domain").collect();
+        assert_eq!(domain.len(), 1);
+        assert_eq!(domain[0].value, "example.com");
+        assert_eq!(domain[0].source_value.as_deref(), Some("john@example.com"));
+        assert_eq!(domain[0].raw_value, None);
+        assert!(derived.iter().any(|f| f.matcher == "customer.name.phonetic"));
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+
+use common::config::GeoMatcherConfig;
+
+use crate::model::{MatcherConfig, MatchingField};
+
+pub const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// Great-circle distance in km between two points given in degrees.
+pub fn haversine_km(lat_a: f64, long_a: f64, lat_b: f64, long_b: f64) -> f64 {
+    let half_sin_squared = |delta: f64| (delta / 2.0).sin().powi(2);
+    let a = half_sin_squared((lat_b - lat_a).to_radians())
+        + lat_a.to_radians().cos() * lat_b.to_radians().cos() * half_sin_squared((long_b - long_a).to_radians());
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// A located field of a geo matcher's source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoLocation {
+    pub matcher: String,
+    pub long: f64,
+    pub lat: f64,
+}
+
+/// Proximity matchers keyed by the name of the matcher they produce.
+#[derive(Debug, Clone, Default)]
+pub struct GeoMatcherRegistry {
+    matchers: HashMap<String, GeoMatcherConfig>,
+}
+
+impl GeoMatcherRegistry {
+    pub fn from_config(configs: &HashMap<String, GeoMatcherConfig>) -> Self {
+        Self { matchers: configs.clone() }
+    }
+
+    pub fn get(&self, matcher: &str) -> Option<&GeoMatcherConfig> {
+        self.matchers.get(matcher)
+    }
+
+    pub fn names(&self) -> Vec<String> {
+        self.matchers.keys().cloned().collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.matchers.is_empty()
+    }
+
+    /// Confidence and importance of the produced matchers, for storage.
+    pub fn matcher_configs(&self) -> HashMap<String, MatcherConfig> {
+        self.matchers
+            .iter()
+            .map(|(name, config)| (name.clone(), (config.confidence, config.importance)))
+            .collect()
+    }
+
+    /// Locations of the fields any geo matcher reads, explicit coordinates win over the ones
+    /// extracted with the field like when matching nodes are saved.
+    pub fn locations(&self, fields: &[MatchingField], explicit: Option<(f64, f64)>) -> Vec<GeoLocation> {
+        let mut names: Vec<&String> = self.matchers.keys().collect();
+        names.sort();
+
+        let mut locations = Vec::new();
+        for name in names {
+            let config = &self.matchers[name];
+            for field in fields.iter().filter(|field| config.sources.contains(&field.matcher)) {
+                let coordinates = explicit.or(field.long_alpha.zip(field.lat_alpha));
+                if let Some((long, lat)) = coordinates.filter(|(long, lat)| is_valid(*long, *lat)) {
+                    locations.push(GeoLocation { matcher: name.clone(), long, lat });
+                }
+            }
+        }
+        locations
+    }
+
+    /// The matcher confidence decayed by distance, `None` outside the radius.
+    pub fn decayed_confidence(&self, matcher: &str, distance_km: f64) -> Option<i32> {
+        let config = self.matchers.get(matcher)?;
+        if config.radius_km <= 0.0 || distance_km > config.radius_km {
+            return None;
+        }
+        Some((config.confidence as f64 * (1.0 - distance_km / config.radius_km)).round() as i32)
+    }
+}
+
+fn is_valid(long: f64, lat: f64) -> bool {
+    (-180.0..=180.0).contains(&long) && (-90.0..=90.0).contains(&lat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> GeoMatcherRegistry {
+        let mut configs = HashMap::new();
+        configs.insert(
+            "address.proximity".to_string(),
+            GeoMatcherConfig {
+                sources: vec!["billing.address".to_string(), "shipment.address".to_string()],
+                radius_km: 2.0,
+                confidence: 60,
+                importance: 40,
+                max_neighbors: 50,
+            },
+        );
+        GeoMatcherRegistry::from_config(&configs)
+    }
+
+    #[test]
+    fn haversine_matches_known_distance() {
+        // Berlin to Lisbon is about 2310 km
+        let distance = haversine_km(52.52, 13.40, 38.72, -9.14);
+        assert!((distance - 2310.0).abs() < 10.0, "{}", distance);
+        assert_eq!(haversine_km(52.52, 13.40, 52.52, 13.40), 0.0);
+    }
+
+    #[test]
+    fn confidence_decays_to_zero_at_the_radius() {
+        let registry = registry();
+        assert_eq!(registry.decayed_confidence("address.proximity", 0.0), Some(60));
+        assert_eq!(registry.decayed_confidence("address.proximity", 1.0), Some(30));
+        assert_eq!(registry.decayed_confidence("address.proximity", 2.5), None);
+        assert_eq!(registry.decayed_confidence("device.id", 0.0), None);
+    }
+
+    #[test]
+    fn locations_are_taken_from_source_fields_with_valid_coordinates() {
+        let now = chrono::Utc::now();
+        let fields = vec![
+            MatchingField::new_with_timespace("billing.address".to_string(), "a".to_string(), now, (13.40, 52.52)),
+            MatchingField::new_with_timespace("shipment.address".to_string(), "b".to_string(), now, (200.0, 52.52)),
+            MatchingField::new_with_timespace("customer.email".to_string(), "c".to_string(), now, (13.40, 52.52)),
+            MatchingField::new_simple("shipment.address".to_string(), "d".to_string()),
+        ];
+
+        let locations = registry().locations(&fields, None);
+        assert_eq!(
+            locations,
+            vec![GeoLocation { matcher: "address.proximity".to_string(), long: 13.40, lat: 52.52 }]
+        );
+    }
+}
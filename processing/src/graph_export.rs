@@ -0,0 +1,142 @@
+use serde_json::{Value, json};
+
+use crate::model::TransactionGraph;
+
+/// GraphML of a transaction graph, for viewers like Gephi or yEd. Node ids are payload numbers.
+pub fn to_graphml(graph: &TransactionGraph) -> String {
+    let mut out = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="depth" for="node" attr.name="depth" attr.type="int"/>
+  <key id="fraud_level" for="node" attr.name="fraud_level" attr.type="string"/>
+  <key id="fraud_category" for="node" attr.name="fraud_category" attr.type="string"/>
+  <key id="score" for="node" attr.name="score" attr.type="int"/>
+  <key id="matcher" for="edge" attr.name="matcher" attr.type="string"/>
+  <key id="value" for="edge" attr.name="value" attr.type="string"/>
+  <key id="confidence" for="edge" attr.name="confidence" attr.type="int"/>
+  <graph id="transactions" edgedefault="undirected">
+"#,
+    );
+
+    for node in &graph.nodes {
+        out.push_str(&format!("    <node id=\"{}\">\n", escape(&node.payload_number)));
+        out.push_str(&format!("      <data key=\"depth\">{}</data>\n", node.depth));
+        if let Some(label) = &node.label {
+            out.push_str(&format!("      <data key=\"fraud_level\">{}</data>\n", label.fraud_level));
+            out.push_str(&format!("      <data key=\"fraud_category\">{}</data>\n", escape(&label.fraud_category)));
+        }
+        if let Some(score) = node.score {
+            out.push_str(&format!("      <data key=\"score\">{}</data>\n", score));
+        }
+        out.push_str("    </node>\n");
+    }
+
+    for edge in &graph.edges {
+        out.push_str(&format!(
+            "    <edge source=\"{}\" target=\"{}\">\n",
+            escape(&edge.source),
+            escape(&edge.target)
+        ));
+        out.push_str(&format!("      <data key=\"matcher\">{}</data>\n", escape(&edge.matcher)));
+        out.push_str(&format!("      <data key=\"value\">{}</data>\n", escape(&edge.value)));
+        out.push_str(&format!("      <data key=\"confidence\">{}</data>\n", edge.confidence));
+        out.push_str("    </edge>\n");
+    }
+
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+/// Cytoscape.js elements JSON, which Cytoscape desktop imports as well.
+pub fn to_cytoscape(graph: &TransactionGraph) -> Value {
+    let nodes: Vec<Value> = graph
+        .nodes
+        .iter()
+        .map(|node| {
+            json!({ "data": {
+                "id": node.payload_number,
+                "depth": node.depth,
+                "fraud_level": node.label.as_ref().map(|label| label.fraud_level.to_string()),
+                "fraud_category": node.label.as_ref().map(|label| &label.fraud_category),
+                "score": node.score,
+            }})
+        })
+        .collect();
+    let edges: Vec<Value> = graph
+        .edges
+        .iter()
+        .map(|edge| {
+            json!({ "data": {
+                "id": format!("{}-{}-{}", edge.source, edge.target, edge.matcher),
+                "source": edge.source,
+                "target": edge.target,
+                "matcher": edge.matcher,
+                "value": edge.value,
+                "confidence": edge.confidence,
+            }})
+        })
+        .collect();
+    json!({ "elements": { "nodes": nodes, "edges": edges } })
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{FraudLevel, LabelSource, TransactionGraphEdge, TransactionGraphNode, mongo_model::Label};
+
+    fn graph() -> TransactionGraph {
+        TransactionGraph {
+            nodes: vec![
+                TransactionGraphNode { payload_number: "A".to_string(), depth: 0, label: None, score: Some(40) },
+                TransactionGraphNode {
+                    payload_number: "B".to_string(),
+                    depth: 1,
+                    label: Some(Label {
+                        fraud_level: FraudLevel::Fraud,
+                        fraud_category: "card".to_string(),
+                        label_source: LabelSource::Manual,
+                        labeled_by: "analyst".to_string(),
+                        created_at: chrono::DateTime::from_timestamp(0, 0).unwrap().naive_utc(),
+                    }),
+                    score: None,
+                },
+            ],
+            edges: vec![TransactionGraphEdge {
+                source: "A".to_string(),
+                target: "B".to_string(),
+                matcher: "customer.email".to_string(),
+                value: "a&b@example.com".to_string(),
+                confidence: 100,
+            }],
+        }
+    }
+
+    #[test]
+    fn graphml_escapes_values_and_skips_missing_data() {
+        let graphml = to_graphml(&graph());
+
+        assert!(graphml.contains("<node id=\"A\">\n      <data key=\"depth\">0</data>\n      <data key=\"score\">40</data>\n    </node>"));
+        assert!(graphml.contains("<data key=\"fraud_level\">Fraud</data>"));
+        assert!(graphml.contains("<edge source=\"A\" target=\"B\">"));
+        assert!(graphml.contains("<data key=\"value\">a&amp;b@example.com</data>"));
+    }
+
+    #[test]
+    fn cytoscape_lists_nodes_and_edges_as_elements() {
+        let elements = to_cytoscape(&graph());
+
+        assert_eq!(elements["elements"]["nodes"][1]["data"]["fraud_level"], "Fraud");
+        assert_eq!(elements["elements"]["nodes"][0]["data"]["fraud_level"], Value::Null);
+        assert_eq!(elements["elements"]["edges"][0]["data"]["id"], "A-B-customer.email");
+        assert_eq!(elements["elements"]["edges"][0]["data"]["confidence"], 100);
+    }
+}
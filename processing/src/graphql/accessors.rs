@@ -1,53 +1,37 @@
-use async_graphql::dynamic::{ListAccessor, ValueAccessor};
+use async_graphql::{
+    Error,
+    dynamic::{ListAccessor, ValueAccessor},
+};
 
 #[inline]
-pub fn access_string(accessor: ValueAccessor) -> String {
-    accessor
-        .string()
-        .expect("Failed to get string in graphql schema.")
-        .to_string()
+pub fn access_string(accessor: ValueAccessor) -> Result<String, Error> {
+    Ok(accessor.string()?.to_string())
 }
 
 #[inline]
-pub fn access_int(accessor: ValueAccessor) -> i64 {
-    accessor
-        .i64()
-        .expect("Failed to get int in graphql schema.")
+pub fn access_int(accessor: ValueAccessor) -> Result<i64, Error> {
+    accessor.i64()
 }
 
 #[inline]
-pub fn access_float(accessor: ValueAccessor) -> f64 {
-    accessor
-        .f64()
-        .expect("Failed to get float in graphql schema.")
+pub fn access_float(accessor: ValueAccessor) -> Result<f64, Error> {
+    accessor.f64()
 }
 
 #[inline]
-pub fn access_bool(accessor: ValueAccessor) -> bool {
-    accessor
-        .boolean()
-        .expect("Failed to get boolean in graphql schema.")
+pub fn access_bool(accessor: ValueAccessor) -> Result<bool, Error> {
+    accessor.boolean()
 }
 
 #[inline]
-pub fn access_array(accessor: ValueAccessor) -> Vec<String> {
-    let list_acc: ListAccessor = accessor
-        .list()
-        .expect("Failed to get list in graphql schema.");
-
-    list_acc
-        .iter()
-        .map(|v| {
-            v.string()
-                .expect("Failed to convert list element to string in graphql schema.")
-                .to_string()
-        })
-        .collect::<Vec<String>>()
+pub fn access_array(accessor: ValueAccessor) -> Result<Vec<String>, Error> {
+    access_list_by(accessor, access_string)
 }
 
-pub fn access_list_by<T, F: Fn(ValueAccessor) -> T>(accessor: ValueAccessor, f: F) -> Vec<T> {
-    let list_acc: ListAccessor = accessor
-        .list()
-        .expect("Failed to get list in graphql schema.");
-    list_acc.iter().map(|v| f(v)).collect::<Vec<T>>()
+pub fn access_list_by<T, F: Fn(ValueAccessor) -> Result<T, Error>>(
+    accessor: ValueAccessor,
+    f: F,
+) -> Result<Vec<T>, Error> {
+    let list_acc: ListAccessor = accessor.list()?;
+    list_acc.iter().map(f).collect::<Result<Vec<T>, Error>>()
 }
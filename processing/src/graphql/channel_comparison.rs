@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use async_graphql::{
+    Value,
+    dynamic::{Field, FieldFuture, FieldValue, InputValue, Object, TypeRef},
+};
+use mongodb::bson::oid::ObjectId;
+
+use super::accessors::{access_int, access_string};
+use crate::{
+    model::{ChannelComparison, RuleCount},
+    shadow::compare_channels,
+    storage::CommonStorage,
+};
+
+pub const DEFAULT_CHANNEL_COMPARISON_LIMIT: i64 = 1000;
+
+fn to_comparison<'a>(parent_value: &'a FieldValue<'a>) -> Result<&'a ChannelComparison, async_graphql::Error> {
+    parent_value
+        .try_downcast_ref::<ChannelComparison>()
+        .map_err(|_| async_graphql::Error::new("Failed to cast channel comparison in graphql schema."))
+}
+
+fn to_rule_count<'a>(parent_value: &'a FieldValue<'a>) -> Result<&'a RuleCount, async_graphql::Error> {
+    parent_value
+        .try_downcast_ref::<RuleCount>()
+        .map_err(|_| async_graphql::Error::new("Failed to cast rule count in graphql schema."))
+}
+
+fn object_id_arg(value: String, name: &str) -> Result<ObjectId, async_graphql::Error> {
+    ObjectId::parse_str(&value).map_err(|e| async_graphql::Error::new(format!("Invalid {}: {}", name, e)))
+}
+
+pub fn rule_count_object() -> Object {
+    Object::new("RuleCount")
+        .description("A rule and the number of transactions it fired for.")
+        .field(
+            Field::new("name", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let rule = to_rule_count(ctx.parent_value)?;
+                    Ok(Some(Value::from(&rule.name)))
+                })
+            })
+            .description("The name of the rule."),
+        )
+        .field(
+            Field::new("count", TypeRef::named_nn(TypeRef::INT), |ctx| {
+                FieldFuture::new(async move {
+                    let rule = to_rule_count(ctx.parent_value)?;
+                    Ok(Some(Value::from(rule.count)))
+                })
+            })
+            .description("The number of transactions."),
+        )
+}
+
+pub fn channel_comparison_object(rule_count_type_name: &str) -> Object {
+    Object::new("ChannelComparison")
+        .description("How a shadow channel scored the transactions also scored by a champion channel.")
+        .field(
+            Field::new("champion_id", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let comparison = to_comparison(ctx.parent_value)?;
+                    Ok(Some(Value::from(comparison.champion_id.to_hex())))
+                })
+            })
+            .description("The internal id of the champion channel."),
+        )
+        .field(
+            Field::new("challenger_id", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let comparison = to_comparison(ctx.parent_value)?;
+                    Ok(Some(Value::from(comparison.challenger_id.to_hex())))
+                })
+            })
+            .description("The internal id of the shadow channel."),
+        )
+        .field(
+            Field::new("compared", TypeRef::named_nn(TypeRef::INT), |ctx| {
+                FieldFuture::new(async move {
+                    let comparison = to_comparison(ctx.parent_value)?;
+                    Ok(Some(Value::from(comparison.compared)))
+                })
+            })
+            .description("The number of transactions scored by both channels."),
+        )
+        .field(
+            Field::new("agreement_rate", TypeRef::named(TypeRef::FLOAT), |ctx| {
+                FieldFuture::new(async move {
+                    let comparison = to_comparison(ctx.parent_value)?;
+                    Ok(comparison.agreement_rate.map(Value::from))
+                })
+            })
+            .description("The share of transactions with the same decision, among those both channels decided."),
+        )
+        .field(
+            Field::new("mean_score_delta", TypeRef::named(TypeRef::FLOAT), |ctx| {
+                FieldFuture::new(async move {
+                    let comparison = to_comparison(ctx.parent_value)?;
+                    Ok(comparison.mean_score_delta.map(Value::from))
+                })
+            })
+            .description("The average of challenger minus champion total score."),
+        )
+        .field(
+            Field::new("mean_abs_score_delta", TypeRef::named(TypeRef::FLOAT), |ctx| {
+                FieldFuture::new(async move {
+                    let comparison = to_comparison(ctx.parent_value)?;
+                    Ok(comparison.mean_abs_score_delta.map(Value::from))
+                })
+            })
+            .description("The average absolute difference of the total scores."),
+        )
+        .field(
+            Field::new("max_abs_score_delta", TypeRef::named(TypeRef::INT), |ctx| {
+                FieldFuture::new(async move {
+                    let comparison = to_comparison(ctx.parent_value)?;
+                    Ok(comparison.max_abs_score_delta.map(Value::from))
+                })
+            })
+            .description("The largest absolute difference of the total scores."),
+        )
+        .field(
+            Field::new("challenger_only_rules", TypeRef::named_nn_list_nn(rule_count_type_name), |ctx| {
+                FieldFuture::new(async move {
+                    let comparison = to_comparison(ctx.parent_value)?;
+                    Ok(Some(FieldValue::list(
+                        comparison.challenger_only_rules.iter().cloned().map(FieldValue::owned_any),
+                    )))
+                })
+            })
+            .description("Rules that fired in the challenger but not in the champion, most frequent first."),
+        )
+}
+
+pub fn channel_comparison_field<ID: Send + Sync + PartialEq + 'static>(comparison_type_name: &str) -> Field {
+    Field::new("channel_comparison", TypeRef::named_nn(comparison_type_name), |ctx| {
+        FieldFuture::new(async move {
+            let storage = ctx.data::<Arc<dyn CommonStorage<ID>>>()?;
+            let champion_id =
+                object_id_arg(ctx.args.try_get("champion_id").and_then(access_string)?, "champion_id")?;
+            let challenger_id =
+                object_id_arg(ctx.args.try_get("challenger_id").and_then(access_string)?, "challenger_id")?;
+            let limit = ctx
+                .args
+                .get("limit")
+                .map(access_int)
+                .transpose()?
+                .unwrap_or(DEFAULT_CHANNEL_COMPARISON_LIMIT);
+            let pairs = storage.get_shadow_event_pairs(champion_id, challenger_id, limit).await?;
+            Ok(Some(FieldValue::owned_any(compare_channels(champion_id, challenger_id, &pairs))))
+        })
+    })
+    .argument(InputValue::new("champion_id", TypeRef::named_nn(TypeRef::STRING)))
+    .argument(InputValue::new("challenger_id", TypeRef::named_nn(TypeRef::STRING)))
+    .argument(InputValue::new("limit", TypeRef::named(TypeRef::INT)))
+    .description("Compares a shadow channel with a champion on the transactions the shadow scored last.")
+}
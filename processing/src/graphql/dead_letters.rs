@@ -0,0 +1,121 @@
+use std::sync::Arc;
+
+use async_graphql::{
+    Value,
+    dynamic::{Field, FieldFuture, FieldValue, InputValue, Object, TypeRef},
+};
+use serde::{Serialize, de::DeserializeOwned};
+
+use super::accessors::{access_int, access_list_by};
+use crate::queue::{DeadLetter, DeadLetterService, QueueMessage};
+
+pub const DEFAULT_DEAD_LETTERS_LIMIT: i64 = 100;
+
+fn to_dead_letter<'a, ID: Send + Sync + 'static>(
+    parent_value: &'a FieldValue<'a>,
+) -> Result<&'a QueueMessage<DeadLetter<ID>>, async_graphql::Error> {
+    parent_value
+        .try_downcast_ref::<QueueMessage<DeadLetter<ID>>>()
+        .map_err(|_| async_graphql::Error::new("Failed to cast dead letter in graphql schema."))
+}
+
+pub fn dead_letter_object<ID: ToString + Send + Sync + 'static>() -> Object {
+    Object::new("DeadLetter")
+        .description("A queue message that exhausted its retries, together with the last error.")
+        .field(
+            Field::new("msg_id", TypeRef::named_nn(TypeRef::INT), |ctx| {
+                FieldFuture::new(async move {
+                    let dl = to_dead_letter::<ID>(ctx.parent_value)?;
+                    Ok(Some(Value::from(dl.msg_id)))
+                })
+            })
+            .description("The id of the message in the dead letter queue."),
+        )
+        .field(
+            Field::new("transaction_id", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let dl = to_dead_letter::<ID>(ctx.parent_value)?;
+                    Ok(Some(Value::from(dl.message.transaction_id.to_string())))
+                })
+            })
+            .description("The internal id of the failed transaction."),
+        )
+        .field(
+            Field::new("source_queue", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let dl = to_dead_letter::<ID>(ctx.parent_value)?;
+                    Ok(Some(Value::from(dl.message.source_queue.to_string())))
+                })
+            })
+            .description("The queue the message is requeued to."),
+        )
+        .field(
+            Field::new("error", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let dl = to_dead_letter::<ID>(ctx.parent_value)?;
+                    Ok(Some(Value::from(&dl.message.error)))
+                })
+            })
+            .description("The error of the last attempt."),
+        )
+        .field(
+            Field::new("read_count", TypeRef::named_nn(TypeRef::INT), |ctx| {
+                FieldFuture::new(async move {
+                    let dl = to_dead_letter::<ID>(ctx.parent_value)?;
+                    Ok(Some(Value::from(dl.message.read_count)))
+                })
+            })
+            .description("The number of attempts made before dead-lettering."),
+        )
+        .field(
+            Field::new("failed_at", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let dl = to_dead_letter::<ID>(ctx.parent_value)?;
+                    Ok(Some(Value::from(dl.message.failed_at.to_rfc3339())))
+                })
+            })
+            .description("The date of the last failure."),
+        )
+}
+
+pub fn dead_letters_field<ID>(dead_letter_type_name: &str) -> Field
+where
+    ID: ToString + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    Field::new("dead_letters", TypeRef::named_nn_list_nn(dead_letter_type_name), |ctx| {
+        FieldFuture::new(async move {
+            let dead_letters = ctx.data::<Arc<dyn DeadLetterService<ID>>>()?;
+            let limit = ctx
+                .args
+                .get("limit")
+                .map(access_int)
+                .transpose()?
+                .unwrap_or(DEFAULT_DEAD_LETTERS_LIMIT);
+            let letters = dead_letters.list_dead_letters(limit).await?;
+            Ok(Some(FieldValue::list(
+                letters.into_iter().map(FieldValue::owned_any),
+            )))
+        })
+    })
+    .argument(InputValue::new("limit", TypeRef::named(TypeRef::INT)))
+    .description("Messages that exhausted their processing retries.")
+}
+
+pub fn requeue_dead_letters_field<ID>() -> Field
+where
+    ID: ToString + Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    Field::new("requeue_dead_letters", TypeRef::named_nn(TypeRef::INT), |ctx| {
+        FieldFuture::new(async move {
+            let dead_letters = ctx.data::<Arc<dyn DeadLetterService<ID>>>()?;
+            let msg_ids = ctx
+                .args
+                .try_get("msg_ids")
+                .and_then(|v| access_list_by(v, access_int))?;
+            let requeued = dead_letters.requeue(&msg_ids).await?;
+            Ok(Some(Value::from(requeued)))
+        })
+    })
+    .argument(InputValue::new("msg_ids", TypeRef::named_nn_list_nn(TypeRef::INT)))
+    .description("Moves dead letters back to their source queues, returns the number requeued.")
+}
@@ -18,21 +18,17 @@ pub fn parse_filters<P: ProcessibleSerde>(
     let filters: Vec<Result<Filter<Box<dyn ColumnValueTrait>>, async_graphql::Error>> =
         column_names
             .map(|col_name| {
-                let filter_object = filters_accsessor
-                    .get(col_name)
-                    .expect("Failed to get filter object in graphql schema.")
-                    .object()?;
+                let filter_object = filters_accsessor.try_get(col_name)?.object()?;
 
                 let op_name = filter_object
                     .keys()
                     .next()
-                    .expect("Failed to get operator in graphql schema.")
+                    .ok_or_else(|| {
+                        async_graphql::Error::new(format!("Missing operator for filter {}.", col_name))
+                    })?
                     .to_string();
 
-                let op_object = filter_object.get(&op_name).expect(&format!(
-                    "Failed to get operator {} in graphql schema.",
-                    op_name
-                ));
+                let op_object = filter_object.try_get(&op_name)?;
 
                 let scalar = column_types
                     .get(&col_name.to_string())
@@ -43,17 +39,17 @@ pub fn parse_filters<P: ProcessibleSerde>(
                     ColumnScalar::String => match op_name {
                         v if v == FilterOperator::Equal(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::Equal(Box::new(
-                                access_string(op_object),
+                                access_string(op_object)?,
                             ))
                         }
                         v if v == FilterOperator::NotEqual(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::NotEqual(Box::new(
-                                access_string(op_object),
+                                access_string(op_object)?,
                             ))
                         }
                         v if v == FilterOperator::Contains(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::Contains(Box::new(
-                                access_string(op_object),
+                                access_string(op_object)?,
                             ))
                         }
                         v if v
@@ -61,7 +57,7 @@ pub fn parse_filters<P: ProcessibleSerde>(
                                 .to_string() =>
                         {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::In(
-                                access_array(op_object)
+                                access_array(op_object)?
                                     .into_iter()
                                     .map(|v| Box::new(v) as Box<dyn ColumnValueTrait>)
                                     .collect(),
@@ -72,7 +68,7 @@ pub fn parse_filters<P: ProcessibleSerde>(
                                 .to_string() =>
                         {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::NotIn(
-                                access_array(op_object)
+                                access_array(op_object)?
                                     .into_iter()
                                     .map(|v| Box::new(v) as Box<dyn ColumnValueTrait>)
                                     .collect(),
@@ -97,17 +93,17 @@ pub fn parse_filters<P: ProcessibleSerde>(
                     ColumnScalar::Int => match op_name {
                         v if v == FilterOperator::Equal(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::Equal(Box::new(
-                                access_int(op_object),
+                                access_int(op_object)?,
                             ))
                         }
                         v if v == FilterOperator::NotEqual(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::NotEqual(Box::new(
-                                access_int(op_object),
+                                access_int(op_object)?,
                             ))
                         }
                         v if v == FilterOperator::GreaterThan(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::GreaterThan(Box::new(
-                                access_int(op_object),
+                                access_int(op_object)?,
                             ))
                         }
                         v if v
@@ -115,44 +111,41 @@ pub fn parse_filters<P: ProcessibleSerde>(
                                 .to_string() =>
                         {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::GreaterThanOrEqual(
-                                Box::new(access_int(op_object)),
+                                Box::new(access_int(op_object)?),
                             )
                         }
                         v if v == FilterOperator::LessThan(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::LessThan(Box::new(
-                                access_int(op_object),
+                                access_int(op_object)?,
                             ))
                         }
                         v if v
                             == FilterOperator::LessThanOrEqual(String::default()).to_string() =>
                         {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::LessThanOrEqual(Box::new(
-                                access_int(op_object),
+                                access_int(op_object)?,
                             ))
                         }
                         v if v
                             == FilterOperator::Between(String::default(), String::default())
                                 .to_string() =>
                         {
-                            let list: Vec<i64> = access_list_by(op_object, access_int);
+                            let list: Vec<i64> = access_list_by(op_object, access_int)?;
+                            let [from, to] = list[..] else {
+                                return Err(async_graphql::Error::new(
+                                    "Between expects exactly two values in graphql schema.",
+                                ));
+                            };
                             FilterOperator::<Box<dyn ColumnValueTrait>>::Between(
-                                Box::new(
-                                    *list
-                                        .get(0)
-                                        .expect("Failed to get first element in graphql schema."),
-                                ),
-                                Box::new(
-                                    *list
-                                        .get(1)
-                                        .expect("Failed to get second element in graphql schema."),
-                                ),
+                                Box::new(from),
+                                Box::new(to),
                             )
                         }
                         v if v
                             == FilterOperator::<Box<dyn ColumnValueTrait>>::In(vec![])
                                 .to_string() =>
                         {
-                            let list: Vec<i64> = access_list_by(op_object, access_int);
+                            let list: Vec<i64> = access_list_by(op_object, access_int)?;
                             FilterOperator::<Box<dyn ColumnValueTrait>>::In(
                                 list.into_iter()
                                     .map(|v| Box::new(v) as Box<dyn ColumnValueTrait>)
@@ -163,7 +156,7 @@ pub fn parse_filters<P: ProcessibleSerde>(
                             == FilterOperator::<Box<dyn ColumnValueTrait>>::NotIn(vec![])
                                 .to_string() =>
                         {
-                            let list: Vec<i64> = access_list_by(op_object, access_int);
+                            let list: Vec<i64> = access_list_by(op_object, access_int)?;
                             FilterOperator::<Box<dyn ColumnValueTrait>>::NotIn(
                                 list.into_iter()
                                     .map(|v| Box::new(v) as Box<dyn ColumnValueTrait>)
@@ -189,17 +182,17 @@ pub fn parse_filters<P: ProcessibleSerde>(
                     ColumnScalar::Float => match op_name {
                         v if v == FilterOperator::Equal(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::Equal(Box::new(
-                                access_float(op_object),
+                                access_float(op_object)?,
                             ))
                         }
                         v if v == FilterOperator::NotEqual(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::NotEqual(Box::new(
-                                access_float(op_object),
+                                access_float(op_object)?,
                             ))
                         }
                         v if v == FilterOperator::GreaterThan(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::GreaterThan(Box::new(
-                                access_float(op_object),
+                                access_float(op_object)?,
                             ))
                         }
                         v if v
@@ -207,44 +200,41 @@ pub fn parse_filters<P: ProcessibleSerde>(
                                 .to_string() =>
                         {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::GreaterThanOrEqual(
-                                Box::new(access_float(op_object)),
+                                Box::new(access_float(op_object)?),
                             )
                         }
                         v if v == FilterOperator::LessThan(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::LessThan(Box::new(
-                                access_float(op_object),
+                                access_float(op_object)?,
                             ))
                         }
                         v if v
                             == FilterOperator::LessThanOrEqual(String::default()).to_string() =>
                         {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::LessThanOrEqual(Box::new(
-                                access_float(op_object),
+                                access_float(op_object)?,
                             ))
                         }
                         v if v
                             == FilterOperator::Between(String::default(), String::default())
                                 .to_string() =>
                         {
-                            let list: Vec<f64> = access_list_by(op_object, access_float);
+                            let list: Vec<f64> = access_list_by(op_object, access_float)?;
+                            let [from, to] = list[..] else {
+                                return Err(async_graphql::Error::new(
+                                    "Between expects exactly two values in graphql schema.",
+                                ));
+                            };
                             FilterOperator::<Box<dyn ColumnValueTrait>>::Between(
-                                Box::new(
-                                    *list
-                                        .get(0)
-                                        .expect("Failed to get first element in graphql schema."),
-                                ),
-                                Box::new(
-                                    *list
-                                        .get(1)
-                                        .expect("Failed to get second element in graphql schema."),
-                                ),
+                                Box::new(from),
+                                Box::new(to),
                             )
                         }
                         v if v
                             == FilterOperator::<Box<dyn ColumnValueTrait>>::In(vec![])
                                 .to_string() =>
                         {
-                            let list: Vec<f64> = access_list_by(op_object, access_float);
+                            let list: Vec<f64> = access_list_by(op_object, access_float)?;
                             FilterOperator::<Box<dyn ColumnValueTrait>>::In(
                                 list.into_iter()
                                     .map(|v| Box::new(v) as Box<dyn ColumnValueTrait>)
@@ -255,7 +245,7 @@ pub fn parse_filters<P: ProcessibleSerde>(
                             == FilterOperator::<Box<dyn ColumnValueTrait>>::NotIn(vec![])
                                 .to_string() =>
                         {
-                            let list: Vec<f64> = access_list_by(op_object, access_float);
+                            let list: Vec<f64> = access_list_by(op_object, access_float)?;
                             FilterOperator::<Box<dyn ColumnValueTrait>>::NotIn(
                                 list.into_iter()
                                     .map(|v| Box::new(v) as Box<dyn ColumnValueTrait>)
@@ -281,12 +271,12 @@ pub fn parse_filters<P: ProcessibleSerde>(
                     ColumnScalar::Boolean => match op_name {
                         v if v == FilterOperator::Equal(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::Equal(Box::new(
-                                access_bool(op_object),
+                                access_bool(op_object)?,
                             ))
                         }
                         v if v == FilterOperator::NotEqual(String::default()).to_string() => {
                             FilterOperator::<Box<dyn ColumnValueTrait>>::NotEqual(Box::new(
-                                access_bool(op_object),
+                                access_bool(op_object)?,
                             ))
                         }
                         v if v
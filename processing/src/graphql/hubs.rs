@@ -0,0 +1,108 @@
+use std::sync::Arc;
+
+use async_graphql::{
+    Value,
+    dynamic::{Field, FieldFuture, FieldValue, InputValue, Object, TypeRef},
+};
+
+use super::accessors::{access_bool, access_int, access_string};
+use crate::{model::HubNode, storage::CommonStorage};
+
+pub const DEFAULT_HUBS_LIMIT: i64 = 100;
+
+fn to_hub_node<'a>(parent_value: &'a FieldValue<'a>) -> Result<&'a HubNode, async_graphql::Error> {
+    parent_value
+        .try_downcast_ref::<HubNode>()
+        .map_err(|_| async_graphql::Error::new("Failed to cast hub node in graphql schema."))
+}
+
+pub fn hub_node_object() -> Object {
+    Object::new("HubNode")
+        .description("A match node shared by so many transactions that it is treated as a hub.")
+        .field(
+            Field::new("matcher", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let hub = to_hub_node(ctx.parent_value)?;
+                    Ok(Some(Value::from(&hub.matcher)))
+                })
+            })
+            .description("The matcher of the node."),
+        )
+        .field(
+            Field::new("value", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let hub = to_hub_node(ctx.parent_value)?;
+                    Ok(Some(Value::from(&hub.value)))
+                })
+            })
+            .description("The matched value."),
+        )
+        .field(
+            Field::new("cardinality", TypeRef::named_nn(TypeRef::INT), |ctx| {
+                FieldFuture::new(async move {
+                    let hub = to_hub_node(ctx.parent_value)?;
+                    Ok(Some(Value::from(hub.cardinality)))
+                })
+            })
+            .description("The number of transactions sharing the value."),
+        )
+        .field(
+            Field::new("is_hub", TypeRef::named_nn(TypeRef::BOOLEAN), |ctx| {
+                FieldFuture::new(async move {
+                    let hub = to_hub_node(ctx.parent_value)?;
+                    Ok(Some(Value::from(hub.is_hub)))
+                })
+            })
+            .description("Whether the cardinality reached the configured hub threshold."),
+        )
+        .field(
+            Field::new("hub_override", TypeRef::named(TypeRef::BOOLEAN), |ctx| {
+                FieldFuture::new(async move {
+                    let hub = to_hub_node(ctx.parent_value)?;
+                    Ok(hub.hub_override.map(Value::from))
+                })
+            })
+            .description("The analyst decision, takes precedence over is_hub when set."),
+        )
+}
+
+pub fn hubs_field<ID: Send + Sync + PartialEq + 'static>(hub_node_type_name: &str) -> Field {
+    Field::new("hubs", TypeRef::named_nn_list_nn(hub_node_type_name), |ctx| {
+        FieldFuture::new(async move {
+            let storage = ctx.data::<Arc<dyn CommonStorage<ID>>>()?;
+            let limit = ctx
+                .args
+                .get("limit")
+                .map(access_int)
+                .transpose()?
+                .unwrap_or(DEFAULT_HUBS_LIMIT);
+            let hubs = storage.list_hubs(limit).await?;
+            Ok(Some(FieldValue::list(hubs.into_iter().map(FieldValue::owned_any))))
+        })
+    })
+    .argument(InputValue::new("limit", TypeRef::named(TypeRef::INT)))
+    .description("Hub match nodes and nodes with an analyst override, largest first.")
+}
+
+pub fn set_hub_override_field<ID: Send + Sync + PartialEq + 'static>() -> Field {
+    Field::new("set_hub_override", TypeRef::named_nn(TypeRef::BOOLEAN), |ctx| {
+        FieldFuture::new(async move {
+            let storage = ctx.data::<Arc<dyn CommonStorage<ID>>>()?;
+            let matcher = ctx.args.try_get("matcher").and_then(access_string)?;
+            let value = ctx.args.try_get("value").and_then(access_string)?;
+            let hub_override = match ctx.args.get("hub_override") {
+                Some(v) if !v.is_null() => Some(access_bool(v)?),
+                _ => None,
+            };
+            let updated = storage.set_hub_override(&matcher, &value, hub_override).await?;
+            Ok(Some(Value::from(updated)))
+        })
+    })
+    .argument(InputValue::new("matcher", TypeRef::named_nn(TypeRef::STRING)))
+    .argument(InputValue::new("value", TypeRef::named_nn(TypeRef::STRING)))
+    .argument(InputValue::new("hub_override", TypeRef::named(TypeRef::BOOLEAN)))
+    .description(
+        "Marks a node as hub (true) or as a regular node (false), null returns it to the threshold. \
+         Returns false when the node does not exist.",
+    )
+}
@@ -12,15 +12,26 @@ use std::time::Instant;
 
 use crate::{
     model::{Processible, ProcessibleSerde, mongo_model::Transaction},
+    queue::DeadLetterService,
     storage::CommonStorage,
 };
 
 mod accessors;
+mod channel_comparison;
+mod dead_letters;
 mod filters;
+mod hubs;
 mod inputs;
 mod payload;
+mod scoring_events;
+mod transaction_graph;
 pub mod types;
 
+pub use channel_comparison::DEFAULT_CHANNEL_COMPARISON_LIMIT;
+pub use dead_letters::DEFAULT_DEAD_LETTERS_LIMIT;
+pub use hubs::DEFAULT_HUBS_LIMIT;
+pub use scoring_events::DEFAULT_SCORING_EVENTS_LIMIT;
+pub use transaction_graph::{DEFAULT_GRAPH_DEPTH, MAX_GRAPH_DEPTH, graph_traversal};
 pub use inputs::{
     FILTER_OPS_BOOL, FILTER_OPS_FLOAT, FILTER_OPS_INT, FILTER_OPS_STRING,
     build_typed_operator_inputs, operator_input_name_for,
@@ -32,10 +43,12 @@ lazy_static::lazy_static! {
     pub static ref LIST_STRING_TYPE: TypeRef = TypeRef::List(Box::new(TypeRef::named_nn(TypeRef::STRING)));
 }
 
-fn to_transaction<'a>(parent_value: &'a FieldValue<'a>) -> &'a Transaction {
+fn to_transaction<'a>(
+    parent_value: &'a FieldValue<'a>,
+) -> Result<&'a Transaction, async_graphql::Error> {
     parent_value
         .try_downcast_ref::<Transaction>()
-        .expect("Failed to cast transaction in graphql schema.")
+        .map_err(|_| async_graphql::Error::new("Failed to cast transaction in graphql schema."))
 }
 
 fn transaction_object<P: ProcessibleSerde>(payload_type_name: &str) -> Object {
@@ -46,7 +59,7 @@ fn transaction_object<P: ProcessibleSerde>(payload_type_name: &str) -> Object {
         .field(
             Field::new("id", TypeRef::named_nn(TypeRef::INT), |ctx| {
                 FieldFuture::new(async move {
-                    let tx = to_transaction(ctx.parent_value);
+                    let tx = to_transaction(ctx.parent_value)?;
                     Ok(Some(Value::from(tx._id.to_hex())))
                 })
             })
@@ -55,7 +68,7 @@ fn transaction_object<P: ProcessibleSerde>(payload_type_name: &str) -> Object {
         .field(
             Field::new("payload", TypeRef::named_nn(payload_type_name), |ctx| {
                 FieldFuture::new(async move {
-                    let tx = to_transaction(ctx.parent_value);
+                    let tx = to_transaction(ctx.parent_value)?;
                     let pl: P = <P as ProcessibleSerde>::from_json(tx.payload.clone())
                         .map_err(|e| async_graphql::Error::new(e.to_string()))?;
                     Ok(Some(FieldValue::owned_any(pl)))
@@ -69,7 +82,7 @@ fn transaction_object<P: ProcessibleSerde>(payload_type_name: &str) -> Object {
                 TypeRef::named_nn(TypeRef::STRING),
                 |ctx| {
                     FieldFuture::new(async move {
-                        let tx = to_transaction(ctx.parent_value);
+                        let tx = to_transaction(ctx.parent_value)?;
                         Ok(Some(Value::from(&tx.payload_number)))
                     })
                 },
@@ -82,7 +95,7 @@ fn transaction_object<P: ProcessibleSerde>(payload_type_name: &str) -> Object {
                 TypeRef::named_nn(TypeRef::INT),
                 |ctx| {
                     FieldFuture::new(async move {
-                        let tx = to_transaction(ctx.parent_value);
+                        let tx = to_transaction(ctx.parent_value)?;
                         Ok(Some(Value::from(tx.schema_version_major)))
                     })
                 },
@@ -95,7 +108,7 @@ fn transaction_object<P: ProcessibleSerde>(payload_type_name: &str) -> Object {
                 TypeRef::named_nn(TypeRef::INT),
                 |ctx| {
                     FieldFuture::new(async move {
-                        let tx = to_transaction(ctx.parent_value);
+                        let tx = to_transaction(ctx.parent_value)?;
                         Ok(Some(Value::from(tx.schema_version_minor)))
                     })
                 },
@@ -105,7 +118,7 @@ fn transaction_object<P: ProcessibleSerde>(payload_type_name: &str) -> Object {
         .field(
             Field::new("fraud_level", TypeRef::named(TypeRef::STRING), |ctx| {
                 FieldFuture::new(async move {
-                    let tx = to_transaction(ctx.parent_value);
+                    let tx = to_transaction(ctx.parent_value)?;
                     Ok(tx.label.as_ref().map(|l| Value::from(l.fraud_level.to_string())))
                 })
             })
@@ -114,7 +127,7 @@ fn transaction_object<P: ProcessibleSerde>(payload_type_name: &str) -> Object {
         .field(
             Field::new("comment", TypeRef::named(TypeRef::STRING), |ctx| {
                 FieldFuture::new(async move {
-                    let tx = to_transaction(ctx.parent_value);
+                    let tx = to_transaction(ctx.parent_value)?;
                     Ok(tx.comment.as_ref().map(Value::from))
                 })
             })
@@ -126,7 +139,7 @@ fn transaction_object<P: ProcessibleSerde>(payload_type_name: &str) -> Object {
                 TypeRef::named(TypeRef::STRING),
                 |ctx| {
                     FieldFuture::new(async move {
-                        let tx = to_transaction(ctx.parent_value);
+                        let tx = to_transaction(ctx.parent_value)?;
                         Ok(tx.last_scoring_date.map(|d| Value::from(d.to_string())))
                     })
                 },
@@ -139,7 +152,7 @@ fn transaction_object<P: ProcessibleSerde>(payload_type_name: &str) -> Object {
                 TypeRef::named_nn(TypeRef::BOOLEAN),
                 |ctx| {
                     FieldFuture::new(async move {
-                        let tx = to_transaction(ctx.parent_value);
+                        let tx = to_transaction(ctx.parent_value)?;
                         Ok(Some(Value::from(tx.processing_complete)))
                     })
                 },
@@ -149,7 +162,7 @@ fn transaction_object<P: ProcessibleSerde>(payload_type_name: &str) -> Object {
         .field(
             Field::new("created_at", TypeRef::named_nn(TypeRef::STRING), |ctx| {
                 FieldFuture::new(async move {
-                    let tx = to_transaction(ctx.parent_value);
+                    let tx = to_transaction(ctx.parent_value)?;
                     Ok(Some(Value::from(tx.created_at.to_string())))
                 })
             })
@@ -159,6 +172,7 @@ fn transaction_object<P: ProcessibleSerde>(payload_type_name: &str) -> Object {
 
 pub fn schema<P: Processible + ProcessibleSerde + 'static>(
     common_storage: Arc<dyn CommonStorage<P::Id>>,
+    dead_letter_queue: Arc<dyn DeadLetterService<P::Id>>,
 ) -> Result<Schema, SchemaError> {
     let (payload, column_types) = payload::build_payload_and_types::<P>();
 
@@ -170,6 +184,15 @@ pub fn schema<P: Processible + ProcessibleSerde + 'static>(
     }
 
     let transaction = transaction_object::<P>(payload.type_name());
+    let dead_letter = dead_letters::dead_letter_object::<P::Id>();
+    let hub_node = hubs::hub_node_object();
+    let graph_node = transaction_graph::graph_node_object();
+    let graph_edge = transaction_graph::graph_edge_object();
+    let graph = transaction_graph::graph_object(graph_node.type_name(), graph_edge.type_name());
+    let rule_score = scoring_events::rule_score_object();
+    let scoring_event = scoring_events::scoring_event_object(rule_score.type_name());
+    let rule_count = channel_comparison::rule_count_object();
+    let comparison = channel_comparison::channel_comparison_object(rule_count.type_name());
 
     let query = Object::new("Query")
         .description("The query object, that contains the transaction object and the payload object.")
@@ -194,10 +217,29 @@ pub fn schema<P: Processible + ProcessibleSerde + 'static>(
             .argument(
                 InputValue::new("filters", TypeRef::named(filters_input_object.type_name()))
             ),
-        );
+        )
+        .field(dead_letters::dead_letters_field::<P::Id>(dead_letter.type_name()))
+        .field(hubs::hubs_field::<P::Id>(hub_node.type_name()))
+        .field(transaction_graph::transaction_graph_field::<P::Id>(graph.type_name()))
+        .field(scoring_events::scoring_events_field::<P::Id>(scoring_event.type_name()))
+        .field(channel_comparison::channel_comparison_field::<P::Id>(comparison.type_name()));
+
+    let mutation = Object::new("Mutation")
+        .description("The mutation object, that contains operational actions.")
+        .field(dead_letters::requeue_dead_letters_field::<P::Id>())
+        .field(hubs::set_hub_override_field::<P::Id>());
 
-    let mut schema = Schema::build(query.type_name(), None, None)
+    let mut schema = Schema::build(query.type_name(), Some(mutation.type_name()), None)
         .register(transaction)
+        .register(dead_letter)
+        .register(hub_node)
+        .register(graph_node)
+        .register(graph_edge)
+        .register(graph)
+        .register(rule_score)
+        .register(scoring_event)
+        .register(rule_count)
+        .register(comparison)
         .register(payload);
 
     for io in inputs::build_typed_operator_inputs() {
@@ -207,7 +249,9 @@ pub fn schema<P: Processible + ProcessibleSerde + 'static>(
     schema
         .register(filters_input_object)
         .register(query)
+        .register(mutation)
         .data(common_storage)
+        .data(dead_letter_queue)
         .data(Arc::new(column_types))
         .finish()
 }
@@ -20,7 +20,9 @@ pub fn build_payload_and_types<P: ProcessibleSerde>() -> (Object, ColumnTypeInde
                         let payload = ctx
                             .parent_value
                             .try_downcast_ref::<P>()
-                            .expect("Failed to cast payload to P in graphql schema.");
+                            .map_err(|_| {
+                                async_graphql::Error::new("Failed to cast payload to P in graphql schema.")
+                            })?;
                         let value = resolver(&payload);
                         Ok(Some(value))
                     })
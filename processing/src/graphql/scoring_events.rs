@@ -0,0 +1,185 @@
+use std::{str::FromStr, sync::Arc};
+
+use async_graphql::{
+    Value,
+    dynamic::{Field, FieldFuture, FieldValue, InputValue, Object, TypeRef},
+};
+use mongodb::bson::oid::ObjectId;
+
+use super::accessors::{access_int, access_string};
+use crate::{
+    model::{
+        Decision, ScoringEventFilter,
+        mongo_model::{RuleScore, ScoringEvent},
+    },
+    storage::CommonStorage,
+};
+
+pub const DEFAULT_SCORING_EVENTS_LIMIT: i64 = 100;
+
+fn to_scoring_event<'a>(parent_value: &'a FieldValue<'a>) -> Result<&'a ScoringEvent, async_graphql::Error> {
+    parent_value
+        .try_downcast_ref::<ScoringEvent>()
+        .map_err(|_| async_graphql::Error::new("Failed to cast scoring event in graphql schema."))
+}
+
+fn to_rule_score<'a>(parent_value: &'a FieldValue<'a>) -> Result<&'a RuleScore, async_graphql::Error> {
+    parent_value
+        .try_downcast_ref::<RuleScore>()
+        .map_err(|_| async_graphql::Error::new("Failed to cast rule score in graphql schema."))
+}
+
+pub fn rule_score_object() -> Object {
+    Object::new("RuleScore")
+        .description("The contribution of a triggered rule to the total score.")
+        .field(
+            Field::new("name", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let rule = to_rule_score(ctx.parent_value)?;
+                    Ok(Some(Value::from(&rule.name)))
+                })
+            })
+            .description("The name of the rule."),
+        )
+        .field(
+            Field::new("score", TypeRef::named_nn(TypeRef::INT), |ctx| {
+                FieldFuture::new(async move {
+                    let rule = to_rule_score(ctx.parent_value)?;
+                    Ok(Some(Value::from(rule.score)))
+                })
+            })
+            .description("The score the rule added."),
+        )
+}
+
+pub fn scoring_event_object(rule_score_type_name: &str) -> Object {
+    Object::new("ScoringEvent")
+        .description("The result of scoring a transaction version in a channel.")
+        .field(
+            Field::new("id", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let event = to_scoring_event(ctx.parent_value)?;
+                    Ok(Some(Value::from(event._id.to_hex())))
+                })
+            })
+            .description("The internal id of the scoring event."),
+        )
+        .field(
+            Field::new("transaction_id", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let event = to_scoring_event(ctx.parent_value)?;
+                    Ok(Some(Value::from(event.transaction_id.to_hex())))
+                })
+            })
+            .description("The internal id of the scored transaction version."),
+        )
+        .field(
+            Field::new("channel_id", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let event = to_scoring_event(ctx.parent_value)?;
+                    Ok(Some(Value::from(event.channel_id.to_hex())))
+                })
+            })
+            .description("The internal id of the scoring channel."),
+        )
+        .field(
+            Field::new("channel_version", TypeRef::named_nn(TypeRef::INT), |ctx| {
+                FieldFuture::new(async move {
+                    let event = to_scoring_event(ctx.parent_value)?;
+                    Ok(Some(Value::from(event.channel_version)))
+                })
+            })
+            .description("The version of the channel that scored the transaction."),
+        )
+        .field(
+            Field::new("total_score", TypeRef::named_nn(TypeRef::INT), |ctx| {
+                FieldFuture::new(async move {
+                    let event = to_scoring_event(ctx.parent_value)?;
+                    Ok(Some(Value::from(event.total_score)))
+                })
+            })
+            .description("The total score: summed rule scores, a model probability in percent, or both."),
+        )
+        .field(
+            Field::new("decision", TypeRef::named(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let event = to_scoring_event(ctx.parent_value)?;
+                    Ok(event.decision.map(|decision| Value::from(decision.to_string())))
+                })
+            })
+            .description("Approve, Review or Decline, unset when the channel has no thresholds."),
+        )
+        .field(
+            Field::new("rule_scores", TypeRef::named_nn_list_nn(rule_score_type_name), |ctx| {
+                FieldFuture::new(async move {
+                    let event = to_scoring_event(ctx.parent_value)?;
+                    Ok(Some(FieldValue::list(event.rule_scores.iter().cloned().map(FieldValue::owned_any))))
+                })
+            })
+            .description("The contribution of each triggered rule or model feature."),
+        )
+        .field(
+            Field::new("triggered_rules", TypeRef::named_nn_list_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let event = to_scoring_event(ctx.parent_value)?;
+                    Ok(Some(Value::List(event.triggered_rules.iter().map(Value::from).collect())))
+                })
+            })
+            .description("The names of the triggered rules."),
+        )
+        .field(
+            Field::new("created_at", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let event = to_scoring_event(ctx.parent_value)?;
+                    Ok(Some(Value::from(event.created_at.to_string())))
+                })
+            })
+            .description("The date of the scoring."),
+        )
+}
+
+pub fn scoring_events_field<ID: Send + Sync + PartialEq + 'static>(scoring_event_type_name: &str) -> Field {
+    Field::new("scoring_events", TypeRef::named_nn_list_nn(scoring_event_type_name), |ctx| {
+        FieldFuture::new(async move {
+            let storage = ctx.data::<Arc<dyn CommonStorage<ID>>>()?;
+            let channel_id = ctx
+                .args
+                .get("channel_id")
+                .map(access_string)
+                .transpose()?
+                .map(|id| ObjectId::parse_str(&id))
+                .transpose()
+                .map_err(|e| async_graphql::Error::new(format!("Invalid channel_id: {}", e)))?;
+            let decision = ctx
+                .args
+                .get("decision")
+                .map(access_string)
+                .transpose()?
+                .map(|decision| Decision::from_str(&decision))
+                .transpose()
+                .map_err(|_| async_graphql::Error::new("decision must be one of Approve, Review, Decline"))?;
+            let filter = ScoringEventFilter {
+                payload_number: ctx.args.get("payload_number").map(access_string).transpose()?,
+                channel_id,
+                decision,
+                min_total_score: ctx.args.get("min_score").map(access_int).transpose()?.map(|min| min as i32),
+                max_total_score: ctx.args.get("max_score").map(access_int).transpose()?.map(|max| max as i32),
+            };
+            let limit = ctx
+                .args
+                .get("limit")
+                .map(access_int)
+                .transpose()?
+                .unwrap_or(DEFAULT_SCORING_EVENTS_LIMIT);
+            let events = storage.find_scoring_events(&filter, limit).await?;
+            Ok(Some(FieldValue::list(events.into_iter().map(FieldValue::owned_any))))
+        })
+    })
+    .argument(InputValue::new("payload_number", TypeRef::named(TypeRef::STRING)))
+    .argument(InputValue::new("channel_id", TypeRef::named(TypeRef::STRING)))
+    .argument(InputValue::new("decision", TypeRef::named(TypeRef::STRING)))
+    .argument(InputValue::new("min_score", TypeRef::named(TypeRef::INT)))
+    .argument(InputValue::new("max_score", TypeRef::named(TypeRef::INT)))
+    .argument(InputValue::new("limit", TypeRef::named(TypeRef::INT)))
+    .description("Scoring events matching all given conditions, newest first.")
+}
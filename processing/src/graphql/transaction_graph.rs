@@ -0,0 +1,209 @@
+use std::sync::Arc;
+
+use async_graphql::{
+    Value,
+    dynamic::{Field, FieldFuture, FieldValue, InputValue, Object, TypeRef},
+};
+use common::config::TraversalConfig;
+
+use super::accessors::{access_int, access_string};
+use crate::{
+    graph_export::{to_cytoscape, to_graphml},
+    model::{TransactionGraph, TransactionGraphEdge, TransactionGraphNode},
+    storage::CommonStorage,
+};
+
+pub const DEFAULT_GRAPH_DEPTH: i64 = 2;
+/// Every hop can multiply the size of the graph, deeper requests are cut to this depth.
+pub const MAX_GRAPH_DEPTH: i64 = 5;
+
+/// The traversal of a graph request, the depth clamped to `1..=MAX_GRAPH_DEPTH` and the
+/// confidence to `0..=100`. The traversal `limit` keeps its default.
+pub fn graph_traversal(depth: Option<i64>, min_confidence: Option<i64>) -> TraversalConfig {
+    let defaults = TraversalConfig::default();
+    TraversalConfig {
+        max_depth: depth.unwrap_or(DEFAULT_GRAPH_DEPTH).clamp(1, MAX_GRAPH_DEPTH) as i32,
+        min_confidence: min_confidence.map_or(defaults.min_confidence, |min| min.clamp(0, 100) as i32),
+        ..defaults
+    }
+}
+
+fn to_graph<'a>(parent_value: &'a FieldValue<'a>) -> Result<&'a TransactionGraph, async_graphql::Error> {
+    parent_value
+        .try_downcast_ref::<TransactionGraph>()
+        .map_err(|_| async_graphql::Error::new("Failed to cast transaction graph in graphql schema."))
+}
+
+fn to_graph_node<'a>(parent_value: &'a FieldValue<'a>) -> Result<&'a TransactionGraphNode, async_graphql::Error> {
+    parent_value
+        .try_downcast_ref::<TransactionGraphNode>()
+        .map_err(|_| async_graphql::Error::new("Failed to cast transaction graph node in graphql schema."))
+}
+
+fn to_graph_edge<'a>(parent_value: &'a FieldValue<'a>) -> Result<&'a TransactionGraphEdge, async_graphql::Error> {
+    parent_value
+        .try_downcast_ref::<TransactionGraphEdge>()
+        .map_err(|_| async_graphql::Error::new("Failed to cast transaction graph edge in graphql schema."))
+}
+
+pub fn graph_node_object() -> Object {
+    Object::new("TransactionGraphNode")
+        .description("The latest version of a transaction in a connected graph.")
+        .field(
+            Field::new("payload_number", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let node = to_graph_node(ctx.parent_value)?;
+                    Ok(Some(Value::from(&node.payload_number)))
+                })
+            })
+            .description("The customer provided id of the transaction."),
+        )
+        .field(
+            Field::new("depth", TypeRef::named_nn(TypeRef::INT), |ctx| {
+                FieldFuture::new(async move {
+                    let node = to_graph_node(ctx.parent_value)?;
+                    Ok(Some(Value::from(node.depth)))
+                })
+            })
+            .description("The number of match nodes on the path from the root, 0 for the root."),
+        )
+        .field(
+            Field::new("fraud_level", TypeRef::named(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let node = to_graph_node(ctx.parent_value)?;
+                    Ok(node.label.as_ref().map(|label| Value::from(label.fraud_level.to_string())))
+                })
+            })
+            .description("The fraud level of the label, if labeled."),
+        )
+        .field(
+            Field::new("fraud_category", TypeRef::named(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let node = to_graph_node(ctx.parent_value)?;
+                    Ok(node.label.as_ref().map(|label| Value::from(&label.fraud_category)))
+                })
+            })
+            .description("The fraud category of the label, if labeled."),
+        )
+        .field(
+            Field::new("score", TypeRef::named(TypeRef::INT), |ctx| {
+                FieldFuture::new(async move {
+                    let node = to_graph_node(ctx.parent_value)?;
+                    Ok(node.score.map(Value::from))
+                })
+            })
+            .description("The highest total score among the latest scorings of each live channel, if scored."),
+        )
+}
+
+pub fn graph_edge_object() -> Object {
+    Object::new("TransactionGraphEdge")
+        .description("Two transactions sharing the value of a matcher.")
+        .field(
+            Field::new("source", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let edge = to_graph_edge(ctx.parent_value)?;
+                    Ok(Some(Value::from(&edge.source)))
+                })
+            })
+            .description("The payload number of one transaction."),
+        )
+        .field(
+            Field::new("target", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let edge = to_graph_edge(ctx.parent_value)?;
+                    Ok(Some(Value::from(&edge.target)))
+                })
+            })
+            .description("The payload number of the other transaction."),
+        )
+        .field(
+            Field::new("matcher", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let edge = to_graph_edge(ctx.parent_value)?;
+                    Ok(Some(Value::from(&edge.matcher)))
+                })
+            })
+            .description("The matcher of the shared node."),
+        )
+        .field(
+            Field::new("value", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let edge = to_graph_edge(ctx.parent_value)?;
+                    Ok(Some(Value::from(&edge.value)))
+                })
+            })
+            .description("The shared value."),
+        )
+        .field(
+            Field::new("confidence", TypeRef::named_nn(TypeRef::INT), |ctx| {
+                FieldFuture::new(async move {
+                    let edge = to_graph_edge(ctx.parent_value)?;
+                    Ok(Some(Value::from(edge.confidence)))
+                })
+            })
+            .description("The confidence of the matcher."),
+        )
+}
+
+pub fn graph_object(node_type_name: &str, edge_type_name: &str) -> Object {
+    Object::new("TransactionGraph")
+        .description("The transactions connected to a payload and the match nodes linking them.")
+        .field(
+            Field::new("nodes", TypeRef::named_nn_list_nn(node_type_name), |ctx| {
+                FieldFuture::new(async move {
+                    let graph = to_graph(ctx.parent_value)?;
+                    Ok(Some(FieldValue::list(graph.nodes.iter().cloned().map(FieldValue::owned_any))))
+                })
+            })
+            .description("The root and its connected transactions, closest first."),
+        )
+        .field(
+            Field::new("edges", TypeRef::named_nn_list_nn(edge_type_name), |ctx| {
+                FieldFuture::new(async move {
+                    let graph = to_graph(ctx.parent_value)?;
+                    Ok(Some(FieldValue::list(graph.edges.iter().cloned().map(FieldValue::owned_any))))
+                })
+            })
+            .description("One edge per pair of transactions and shared node."),
+        )
+        .field(
+            Field::new("graphml", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let graph = to_graph(ctx.parent_value)?;
+                    Ok(Some(Value::from(to_graphml(graph))))
+                })
+            })
+            .description("The graph as GraphML document."),
+        )
+        .field(
+            Field::new("cytoscape", TypeRef::named_nn(TypeRef::STRING), |ctx| {
+                FieldFuture::new(async move {
+                    let graph = to_graph(ctx.parent_value)?;
+                    Ok(Some(Value::from(to_cytoscape(graph).to_string())))
+                })
+            })
+            .description("The graph as Cytoscape elements JSON."),
+        )
+}
+
+pub fn transaction_graph_field<ID: Send + Sync + PartialEq + 'static>(graph_type_name: &str) -> Field {
+    Field::new("transactionGraph", TypeRef::named_nn(graph_type_name), |ctx| {
+        FieldFuture::new(async move {
+            let storage = ctx.data::<Arc<dyn CommonStorage<ID>>>()?;
+            let payload_number = ctx.args.try_get("payloadNumber").and_then(access_string)?;
+            let depth = ctx.args.get("depth").map(access_int).transpose()?;
+            let min_confidence = ctx.args.get("minConfidence").map(access_int).transpose()?;
+            let traversal = graph_traversal(depth, min_confidence);
+            let graph = storage.get_transaction_graph(&payload_number, &traversal).await?;
+            Ok(Some(FieldValue::owned_any(graph)))
+        })
+    })
+    .argument(InputValue::new("payloadNumber", TypeRef::named_nn(TypeRef::STRING)))
+    .argument(InputValue::new("depth", TypeRef::named(TypeRef::INT)))
+    .argument(InputValue::new("minConfidence", TypeRef::named(TypeRef::INT)))
+    .description(format!(
+        "The graph of transactions connected to a payload, with GraphML and Cytoscape exports. The depth is capped at {}.",
+        MAX_GRAPH_DEPTH
+    ))
+}
@@ -0,0 +1,121 @@
+use std::{collections::HashSet, fmt::Debug, sync::Arc};
+
+use common::config::TraversalConfig;
+use serde::{Serialize, de::DeserializeOwned};
+#[cfg(not(test))]
+use tracing::{debug, info};
+#[cfg(test)]
+use {println as debug, println as info};
+
+use crate::{
+    error::Result,
+    model::{FraudLevel, LabelSource},
+    queue::QueueService,
+    storage::CommonStorage,
+};
+
+/// Labels transactions and queues their neighbors for recalculation, so graph features built
+/// from neighbor labels follow the verdict. Every labeling entry point goes through here.
+pub struct Labeler<ID: Send + Sync + PartialEq + Serialize + DeserializeOwned> {
+    storage: Arc<dyn CommonStorage<ID>>,
+    recalc_queue: Arc<dyn QueueService<ID>>,
+    traversal: TraversalConfig,
+}
+
+impl<ID> Labeler<ID>
+where
+    ID: Send + Sync + PartialEq + Serialize + DeserializeOwned + Clone + Debug + 'static,
+{
+    /// `traversal` bounds how far from a labeled transaction neighbors are recalculated.
+    pub fn new(
+        storage: Arc<dyn CommonStorage<ID>>,
+        recalc_queue: Arc<dyn QueueService<ID>>,
+        traversal: TraversalConfig,
+    ) -> Self {
+        Self { storage, recalc_queue, traversal }
+    }
+
+    /// Writes the label and returns the number of neighbors queued for recalculation.
+    pub async fn label(
+        &self,
+        payload_numbers: &[String],
+        fraud_level: &FraudLevel,
+        fraud_category: &String,
+        label_source: &LabelSource,
+        labeled_by: &String,
+    ) -> Result<usize> {
+        self.storage
+            .label_transactions(payload_numbers, fraud_level, fraud_category, label_source, labeled_by)
+            .await?;
+
+        let mut connected = Vec::new();
+        for payload_number in payload_numbers {
+            let transactions = self
+                .storage
+                .find_connected_transactions(payload_number, &self.traversal, None)
+                .await?;
+            let direct = self.storage.get_direct_connections(payload_number).await?;
+            connected.push((
+                payload_number.as_str(),
+                transactions
+                    .into_iter()
+                    .map(|ct| ct.payload_number)
+                    .chain(direct.into_iter().map(|dc| dc.payload_number))
+                    .collect(),
+            ));
+        }
+        let neighbors = neighbor_payload_numbers(connected);
+        if neighbors.is_empty() {
+            return Ok(0);
+        }
+
+        let ids = self.storage.get_latest_transaction_ids(&neighbors).await?;
+        let enqueued = self.recalc_queue.is_enqueued(&ids).await?;
+        let ids: Vec<ID> = ids.into_iter().filter(|id| !enqueued.contains(id)).collect();
+        self.recalc_queue.enqueue(&ids).await?;
+
+        info!(
+            "Labeled {} transactions, queued {} neighbors for recalculation",
+            payload_numbers.len(),
+            ids.len()
+        );
+        debug!("Neighbors queued after labeling: {:?}", ids);
+        Ok(ids.len())
+    }
+}
+
+/// Neighbors of all labeled transactions, by traversal or direct (fuzzy and geo) connection, once
+/// each. A labeled transaction connected to another labeled one is a neighbor too, its graph
+/// features count the other's label.
+fn neighbor_payload_numbers(connected: Vec<(&str, Vec<String>)>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    connected
+        .into_iter()
+        .flat_map(|(labeled, neighbors)| neighbors.into_iter().filter(move |neighbor| neighbor != labeled))
+        .filter(|neighbor| seen.insert(neighbor.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connected<'a>(labeled: &'a str, neighbors: &[&str]) -> (&'a str, Vec<String>) {
+        (labeled, neighbors.iter().map(|neighbor| neighbor.to_string()).collect())
+    }
+
+    #[test]
+    fn neighbors_are_deduplicated_and_include_labeled_neighbors() {
+        let connected = vec![
+            connected("A", &["B", "C", "D"]),
+            connected("B", &["A", "D", "E"]),
+        ];
+
+        assert_eq!(neighbor_payload_numbers(connected), vec!["B", "C", "D", "A", "E"]);
+    }
+
+    #[test]
+    fn labeled_transactions_are_not_their_own_neighbors() {
+        assert_eq!(neighbor_payload_numbers(vec![connected("A", &["A", "C"])]), vec!["C"]);
+    }
+}
@@ -1,9 +1,20 @@
 #![feature(impl_trait_in_bindings)]
+pub mod error;
 pub mod executable_utils;
+pub mod fraud_features;
+pub mod fuzzy;
+pub mod geo;
+pub mod graph_export;
 pub mod graphql;
 pub mod importer;
+pub mod labeling;
 pub mod model;
+pub mod normalizers;
 pub mod processor;
 pub mod queue;
 pub mod scorers;
+pub mod shadow;
 pub mod storage;
+pub mod velocity;
+
+pub use error::Error;
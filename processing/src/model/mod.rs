@@ -1,9 +1,10 @@
 use chrono::{DateTime, Utc, serde::ts_seconds};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::error::Error;
 use std::fmt::Debug;
 use evalexpr::Value as EvalValue;
-use strum_macros::Display as EnumDisplay;
+use strum_macros::{Display as EnumDisplay, EnumString};
 
 pub type ModelId = i64;
 
@@ -26,7 +27,14 @@ pub type GenericError = Box<dyn Error + Send + Sync>;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MatchingField {
     pub matcher: String,
+    /// The normalized value nodes are matched by.
     pub value: String,
+    /// The value as extracted, when normalization changed it.
+    #[serde(default)]
+    pub raw_value: Option<String>,
+    /// The value of the source field a fuzzy matcher derived this blocking key from.
+    #[serde(default)]
+    pub source_value: Option<String>,
     pub datetime_alpha: Option<DateTime<Utc>>,
     pub datetime_beta: Option<DateTime<Utc>>,
     pub long_alpha: Option<f64>,
@@ -44,6 +52,8 @@ impl MatchingField {
         Self {
             matcher,
             value,
+            raw_value: None,
+            source_value: None,
             datetime_alpha: None,
             datetime_beta: None,
             long_alpha: None,
@@ -66,6 +76,8 @@ impl MatchingField {
         Self {
             matcher,
             value,
+            raw_value: None,
+            source_value: None,
             datetime_alpha: Some(created_at),
             datetime_beta: None,
             long_alpha: Some(location.0),
@@ -92,6 +104,8 @@ impl MatchingField {
         Self {
             matcher,
             value,
+            raw_value: None,
+            source_value: None,
             datetime_alpha,
             datetime_beta,
             long_alpha: location_alpha.map(|(l, _)| l),
@@ -111,6 +125,9 @@ pub struct ConnectedTransaction {
     pub payload_number: String,
     pub path: Vec<String>,
     pub total_confidence: i32,
+    /// Label of the neighbor's latest version, set by the processor before feature extraction.
+    #[serde(default)]
+    pub label: Option<mongo_model::Label>,
 }
 
 impl PartialEq for ConnectedTransaction {
@@ -127,10 +144,162 @@ pub struct DirectConnection {
     pub matcher: String,
     pub confidence: i32,
     pub importance: i32,
+    /// Distance between the transactions, for geo matchers.
+    #[serde(default)]
+    pub distance_km: Option<f64>,
+    /// Label of the neighbor's latest version, set by the processor before feature extraction.
+    #[serde(default)]
+    pub label: Option<mongo_model::Label>,
+}
+
+/// A match node linking many transactions, as listed for analysts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HubNode {
+    pub matcher: String,
+    pub value: String,
+    pub cardinality: i64,
+    pub is_hub: bool,
+    pub hub_override: Option<bool>,
+}
+
+/// The connected transactions of a payload and the match nodes linking them, for investigation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransactionGraph {
+    pub nodes: Vec<TransactionGraphNode>,
+    pub edges: Vec<TransactionGraphEdge>,
+}
+
+/// The latest version of a transaction in a `TransactionGraph`, `depth` 0 is the root.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransactionGraphNode {
+    pub payload_number: String,
+    pub depth: i32,
+    pub label: Option<mongo_model::Label>,
+    /// Highest total score among the latest events of each live channel on the latest version.
+    pub score: Option<i32>,
+}
+
+/// Two transactions sharing the value of a matcher.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TransactionGraphEdge {
+    pub source: String,
+    pub target: String,
+    pub matcher: String,
+    pub value: String,
+    pub confidence: i32,
+}
+
+/// How a shadow channel scored the transactions also scored by a champion channel.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelComparison {
+    pub champion_id: mongodb::bson::oid::ObjectId,
+    pub challenger_id: mongodb::bson::oid::ObjectId,
+    /// Transactions scored by both channels.
+    pub compared: usize,
+    /// Share of transactions with the same decision, among those both channels decided.
+    pub agreement_rate: Option<f64>,
+    /// Challenger minus champion total score, averaged.
+    pub mean_score_delta: Option<f64>,
+    pub mean_abs_score_delta: Option<f64>,
+    pub max_abs_score_delta: Option<i32>,
+    /// Rules that fired in the challenger but not in the champion, most frequent first.
+    pub challenger_only_rules: Vec<RuleCount>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleCount {
+    pub name: String,
+    pub count: usize,
+}
+
+/// Conditions on listed scoring events, unset fields match everything.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ScoringEventFilter {
+    /// Events of any version of the transaction.
+    pub payload_number: Option<String>,
+    pub channel_id: Option<mongodb::bson::oid::ObjectId>,
+    pub decision: Option<Decision>,
+    pub min_total_score: Option<i32>,
+    pub max_total_score: Option<i32>,
+}
+
+/// The latest version of a neighbor, as much as needed to decide whether to recalculate it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NeighborSummary<ID> {
+    pub id: ID,
+    pub payload_number: String,
+    /// Creation time of the first version, re-imports don't make an old neighbor look new.
+    pub created_at: chrono::NaiveDateTime,
+    pub is_labeled: bool,
+}
+
+/// The latest version of a neighbor with the simple features listed in `processor.neighbor_features`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NeighborSnapshot {
+    pub payload_number: String,
+    /// Creation time of the first version, re-imports don't move a neighbor in time.
+    pub created_at: chrono::NaiveDateTime,
+    pub features: Vec<Feature>,
+    pub label: Option<mongo_model::Label>,
+}
+
+impl NeighborSnapshot {
+    pub fn feature(&self, name: &str) -> Option<&FeatureValue> {
+        self.features.iter().find(|feature| feature.name == name).map(|feature| feature.value.as_ref())
+    }
+}
+
+/// The neighbors of a transaction keyed by payload number, as seen when the transaction itself
+/// was created.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Neighborhood {
+    pub created_at: chrono::NaiveDateTime,
+    /// When the features are computed, labels age against it. Defaults to now.
+    pub scored_at: chrono::NaiveDateTime,
+    pub neighbors: HashMap<String, NeighborSnapshot>,
+}
+
+impl Neighborhood {
+    pub fn new(created_at: chrono::NaiveDateTime, neighbors: Vec<NeighborSnapshot>) -> Self {
+        Self {
+            created_at,
+            scored_at: Utc::now().naive_utc(),
+            neighbors: neighbors
+                .into_iter()
+                .map(|neighbor| (neighbor.payload_number.clone(), neighbor))
+                .collect(),
+        }
+    }
+
+    pub fn with_scored_at(mut self, scored_at: chrono::NaiveDateTime) -> Self {
+        self.scored_at = scored_at;
+        self
+    }
+
+    /// Copies the neighbor labels onto the connections.
+    pub fn label_connections(
+        &self,
+        connected_transactions: &mut [ConnectedTransaction],
+        direct_connections: &mut [DirectConnection],
+    ) {
+        let label_of = |payload_number: &str| {
+            self.neighbors.get(payload_number).and_then(|neighbor| neighbor.label.clone())
+        };
+        for ct in connected_transactions.iter_mut() {
+            ct.label = label_of(&ct.payload_number);
+        }
+        for dc in direct_connections.iter_mut() {
+            dc.label = label_of(&dc.payload_number);
+        }
+    }
 }
 
 pub trait ScoringResult: Send + Sync {
     fn get_total_score(&self) -> i32;
+    /// Names of the rules that fired, empty for models without rules.
+    fn get_triggered_rules(&self) -> Vec<String>;
+    /// Contributions to the total score, one per rule or feature.
+    fn get_rule_scores(&self) -> Vec<mongo_model::RuleScore>;
     fn get_result_payload(&self) -> serde_json::Value;
 }
 
@@ -138,6 +307,14 @@ impl ScoringResult for Vec<ExpressionRule> {
     fn get_total_score(&self) -> i32 {
         self.iter().map(|rule| rule.score).sum()
     }
+    fn get_triggered_rules(&self) -> Vec<String> {
+        self.iter().map(|rule| rule.name.clone()).collect()
+    }
+    fn get_rule_scores(&self) -> Vec<mongo_model::RuleScore> {
+        self.iter()
+            .map(|rule| mongo_model::RuleScore { name: rule.name.clone(), score: rule.score })
+            .collect()
+    }
     fn get_result_payload(&self) -> serde_json::Value {
         serde_json::json!(
             self.iter().map(|rule| rule.name.clone()).collect::<Vec<String>>()
@@ -163,6 +340,21 @@ pub enum FraudLevel {
     NotCreditWorthy,
 }
 
+impl FraudLevel {
+    /// Levels that confirm or strongly suggest fraud, as opposed to credit or clean labels.
+    pub fn is_fraud(&self) -> bool {
+        matches!(self, FraudLevel::Fraud | FraudLevel::AccountTakeover | FraudLevel::BlockedAutomatically)
+    }
+}
+
+/// Outcome of a scoring, from the thresholds of the channel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumDisplay, EnumString)]
+pub enum Decision {
+    Approve,
+    Review,
+    Decline,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumDisplay)]
 pub enum LabelSource {
     Manual,
@@ -1,8 +1,8 @@
 use chrono::NaiveDateTime;
-use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{Document, oid::ObjectId};
 use serde::{Deserialize, Serialize};
 
-use crate::model::{ExpressionRule, Feature, FraudLevel, LabelSource};
+use crate::model::{Decision, ExpressionRule, Feature, FraudLevel, LabelSource};
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Label {
@@ -27,36 +27,160 @@ pub struct Transaction {
     pub comment: Option<String>,
     pub last_scoring_date: Option<NaiveDateTime>,
     pub processing_complete: bool,
+    /// Match nodes this version links to, `None` until its matching fields are saved.
+    #[serde(default)]
+    pub matching_fields: Option<Vec<MatchingFieldKey>>,
+    /// Creation time of the first version, carried over on re-import. `None` on versions stored
+    /// before it was recorded.
+    #[serde(default)]
+    pub first_created_at: Option<NaiveDateTime>,
     pub created_at: NaiveDateTime,
     pub updated_at: NaiveDateTime,
 }
 
+impl Transaction {
+    /// When the payload was first seen, the time it is ordered by among its neighbors.
+    pub fn order_time(&self) -> NaiveDateTime {
+        self.first_created_at.unwrap_or(self.created_at)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MatchingFieldKey {
+    pub matcher: String,
+    pub value: String,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ScoringModel {
     pub name: String,
     pub features_schema_version_major: i32,
     pub features_schema_version_minor: i32,
     pub version: String,
-    //TODO: should be made model type agnostic payload
-    pub expression_rules: Vec<ExpressionRule>,
-    pub model_type: super::ScoringModelType,
+    pub payload: ModelPayload,
     pub created_at: NaiveDateTime,
 }
 
+/// What a scorer needs to run the model, tagged by `kind`. The `ScorerFactory` picks the scorer
+/// by `ModelPayload::kind`, scorers registered outside this crate use `Custom`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ModelPayload {
+    ExpressionBased {
+        expression_rules: Vec<ExpressionRule>,
+    },
+    MachineLearning {
+        /// Path to the model file.
+        artifact_path: String,
+    },
+    /// Scores of triggered rules are added to the model probability in percent.
+    Hybrid {
+        expression_rules: Vec<ExpressionRule>,
+        artifact_path: String,
+    },
+    Custom {
+        /// The name the scorer is registered under.
+        scorer: String,
+        #[serde(default)]
+        config: Document,
+    },
+}
+
+impl ModelPayload {
+    pub const EXPRESSION_BASED: &'static str = "expression_based";
+    pub const MACHINE_LEARNING: &'static str = "machine_learning";
+    pub const HYBRID: &'static str = "hybrid";
+
+    pub fn kind(&self) -> &str {
+        match self {
+            ModelPayload::ExpressionBased { .. } => Self::EXPRESSION_BASED,
+            ModelPayload::MachineLearning { .. } => Self::MACHINE_LEARNING,
+            ModelPayload::Hybrid { .. } => Self::HYBRID,
+            ModelPayload::Custom { scorer, .. } => scorer,
+        }
+    }
+
+    /// Empty for payloads without rules.
+    pub fn expression_rules(&self) -> &[ExpressionRule] {
+        match self {
+            ModelPayload::ExpressionBased { expression_rules } | ModelPayload::Hybrid { expression_rules, .. } => {
+                expression_rules
+            }
+            _ => &[],
+        }
+    }
+
+    pub fn artifact_path(&self) -> Option<&str> {
+        match self {
+            ModelPayload::MachineLearning { artifact_path } | ModelPayload::Hybrid { artifact_path, .. } => {
+                Some(artifact_path)
+            }
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ScoringEvent {
     pub _id: ObjectId,
     pub transaction_id: ObjectId,
     pub channel_id: ObjectId,
+    /// The version of the channel at scoring time.
+    #[serde(default)]
+    pub channel_version: i64,
     pub triggered_rules: Vec<String>,
+    #[serde(default)]
+    pub total_score: i32,
+    #[serde(default)]
+    pub rule_scores: Vec<RuleScore>,
+    /// `None` when the channel has no thresholds.
+    #[serde(default)]
+    pub decision: Option<Decision>,
     pub created_at: NaiveDateTime,
 }
 
+/// Contribution of a rule to the total score of a scoring event.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RuleScore {
+    pub name: String,
+    pub score: i32,
+}
+
+/// Total scores at or above `decline` are declined, at or above `review` reviewed and approved
+/// below.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DecisionThresholds {
+    pub review: i32,
+    pub decline: i32,
+}
+
+impl DecisionThresholds {
+    pub fn decide(&self, total_score: i32) -> Decision {
+        if total_score >= self.decline {
+            Decision::Decline
+        } else if total_score >= self.review {
+            Decision::Review
+        } else {
+            Decision::Approve
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ScoringChannel {
     pub _id: ObjectId,
     pub channel_name: String,
     pub model: ScoringModel,
+    #[serde(default)]
+    pub thresholds: Option<DecisionThresholds>,
+    /// Incremented on every edit of the channel, recorded on its scoring events. The processor
+    /// refuses to reload an edited channel whose version did not change.
+    #[serde(default)]
+    pub version: i64,
+    /// Scores alongside the other active channels, but its events are stored apart and never
+    /// drive decisions.
+    #[serde(default)]
+    pub shadow: bool,
     pub is_active: bool,
     pub created_at: NaiveDateTime,
 }
@@ -73,17 +197,81 @@ pub struct FeaturesSet {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MatchNode {
     pub _id: ObjectId,
+    /// The most recent links, older ones overflow into `MatchNodeBucket`s.
+    #[serde(default)]
     pub transaction_data: Vec<MatchNodeTransaction>,
     pub payload_numbers: Vec<String>,
     pub matcher: String,
     pub value: String,
     pub confidence: i32,
     pub importance: i32,
+    /// Number of distinct payload numbers linked by the node.
+    #[serde(default)]
+    pub cardinality: i64,
+    /// Set automatically once `cardinality` reaches the hub threshold.
+    #[serde(default)]
+    pub is_hub: bool,
+    /// Analyst decision that wins over `is_hub`: `true` for known junk values, `false` for
+    /// legitimately shared ones.
+    #[serde(default)]
+    pub hub_override: Option<bool>,
+    /// Number of links ever saved, inline and overflowed.
+    #[serde(default)]
+    pub link_count: i64,
+    /// Set once more payload numbers linked the node than `payload_numbers` keeps.
+    #[serde(default)]
+    pub payload_numbers_capped: bool,
+}
+
+impl MatchNode {
+    pub fn is_effective_hub(&self) -> bool {
+        self.hub_override.unwrap_or(self.is_hub)
+    }
+}
+
+/// Location of a transaction for a geo matcher, one per source field.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GeoPoint {
+    pub _id: ObjectId,
+    pub payload_number: String,
+    pub matcher: String,
+    pub location: GeoJsonPoint,
+    pub created_at: NaiveDateTime,
+}
+
+/// GeoJSON point as required by the 2dsphere index, coordinates are `[long, lat]`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GeoJsonPoint {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub coordinates: [f64; 2],
+}
+
+impl GeoJsonPoint {
+    pub fn new(long: f64, lat: f64) -> Self {
+        Self { kind: "Point".to_string(), coordinates: [long, lat] }
+    }
+}
+
+/// Links of a match node that no longer fit inline, `count` of them in `transaction_data`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MatchNodeBucket {
+    pub _id: ObjectId,
+    pub matcher: String,
+    pub value: String,
+    pub count: i64,
+    pub transaction_data: Vec<MatchNodeTransaction>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MatchNodeTransaction {
     pub payload_number: String,
+    /// The value before normalization, for display.
+    #[serde(default)]
+    pub raw_value: Option<String>,
+    /// The source value of a fuzzy link, candidates sharing the key are compared by it.
+    #[serde(default)]
+    pub source_value: Option<String>,
     pub datetime_alpha: Option<NaiveDateTime>,
     pub datetime_beta: Option<NaiveDateTime>,
     pub long_alpha: Option<f64>,
@@ -96,3 +284,18 @@ pub struct MatchNodeTransaction {
     pub lat_delta: Option<f64>,
     pub created_at: NaiveDateTime,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn thresholds_decide_inclusively_from_the_highest() {
+        let thresholds = DecisionThresholds { review: 50, decline: 80 };
+
+        assert_eq!(thresholds.decide(49), Decision::Approve);
+        assert_eq!(thresholds.decide(50), Decision::Review);
+        assert_eq!(thresholds.decide(79), Decision::Review);
+        assert_eq!(thresholds.decide(80), Decision::Decline);
+    }
+}
@@ -4,7 +4,7 @@ use std::str::FromStr;
 use std::sync::Arc;
 
 use crate::model::Feature;
-use crate::model::{ConnectedTransaction, DirectConnection, MatchingField};
+use crate::model::{ConnectedTransaction, DirectConnection, MatchingField, Neighborhood};
 use async_graphql::Value;
 use async_graphql::dynamic::TypeRef;
 use async_trait::async_trait;
@@ -39,6 +39,7 @@ pub trait Processible: Send + Sync + Clone + Serialize + DeserializeOwned + 'sta
         &self,
         connected_transactions: &[ConnectedTransaction],
         direct_connections: &[DirectConnection],
+        neighborhood: &Neighborhood,
     ) -> Vec<Feature>;
 
     fn extract_matching_fields(&self) -> Vec<MatchingField>;
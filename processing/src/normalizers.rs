@@ -0,0 +1,257 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use common::config::NormalizerConfig;
+use unicode_normalization::{UnicodeNormalization, char::is_combining_mark};
+
+use crate::model::MatchingField;
+
+/// Turns a raw matching value into the form it is matched by.
+pub trait Normalizer: Send + Sync {
+    fn normalize(&self, value: &str) -> String;
+}
+
+pub struct Trim;
+
+impl Normalizer for Trim {
+    fn normalize(&self, value: &str) -> String {
+        value.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+}
+
+pub struct Lowercase;
+
+impl Normalizer for Lowercase {
+    fn normalize(&self, value: &str) -> String {
+        value.to_lowercase()
+    }
+}
+
+pub struct UnicodeFold;
+
+impl Normalizer for UnicodeFold {
+    fn normalize(&self, value: &str) -> String {
+        value.nfkd().filter(|c| !is_combining_mark(*c)).collect()
+    }
+}
+
+pub struct Email;
+
+impl Normalizer for Email {
+    fn normalize(&self, value: &str) -> String {
+        let email = value.trim().to_lowercase();
+        let Some((local, domain)) = email.rsplit_once('@') else {
+            return email;
+        };
+        match domain {
+            "gmail.com" | "googlemail.com" => {
+                let local = local.split('+').next().unwrap_or_default().replace('.', "");
+                format!("{}@gmail.com", local)
+            }
+            _ => email,
+        }
+    }
+}
+
+pub struct Phone {
+    pub default_country_code: Option<String>,
+}
+
+impl Normalizer for Phone {
+    fn normalize(&self, value: &str) -> String {
+        let value = value.trim();
+        let digits: String = value.chars().filter(char::is_ascii_digit).collect();
+        // Placeholders like "n/a" carry no number, a bare prefix would link all of them
+        if digits.trim_start_matches('0').is_empty() {
+            return String::new();
+        }
+        if value.starts_with('+') {
+            return format!("+{}", digits);
+        }
+        if let Some(international) = digits.strip_prefix("00") {
+            return format!("+{}", international);
+        }
+        match &self.default_country_code {
+            Some(country_code) => {
+                let country_code = country_code.trim_start_matches('+');
+                format!("+{}{}", country_code, digits.trim_start_matches('0'))
+            }
+            None => digits,
+        }
+    }
+}
+
+pub struct Address;
+
+impl Address {
+    const ABBREVIATIONS: [(&'static str, &'static str); 10] = [
+        ("street", "st"),
+        ("avenue", "ave"),
+        ("road", "rd"),
+        ("boulevard", "blvd"),
+        ("drive", "dr"),
+        ("lane", "ln"),
+        ("court", "ct"),
+        ("place", "pl"),
+        ("square", "sq"),
+        ("apartment", "apt"),
+    ];
+    /// German street types are written together with the name, "hauptstrasse" -> "hauptstr". A
+    /// token that is only the suffix is left alone.
+    const SUFFIXES: [(&'static str, &'static str); 2] = [("strasse", "str"), ("platz", "pl")];
+
+    fn canonical_token(token: &str) -> String {
+        if let Some((_, abbreviation)) = Self::ABBREVIATIONS.iter().find(|(word, _)| *word == token) {
+            return abbreviation.to_string();
+        }
+        Self::SUFFIXES
+            .iter()
+            .find_map(|(suffix, abbreviation)| {
+                token
+                    .strip_suffix(suffix)
+                    .filter(|stem| !stem.is_empty())
+                    .map(|stem| format!("{}{}", stem, abbreviation))
+            })
+            .unwrap_or_else(|| token.to_string())
+    }
+}
+
+impl Normalizer for Address {
+    fn normalize(&self, value: &str) -> String {
+        // "ß" has no decomposition, spell it out before folding
+        let folded = UnicodeFold.normalize(&value.to_lowercase().replace('ß', "ss"));
+        folded
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|token| !token.is_empty())
+            .map(Self::canonical_token)
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+fn build_normalizer(config: &NormalizerConfig) -> Arc<dyn Normalizer> {
+    match config {
+        NormalizerConfig::Trim => Arc::new(Trim),
+        NormalizerConfig::Lowercase => Arc::new(Lowercase),
+        NormalizerConfig::UnicodeFold => Arc::new(UnicodeFold),
+        NormalizerConfig::Email => Arc::new(Email),
+        NormalizerConfig::Phone { default_country_code } => Arc::new(Phone {
+            default_country_code: default_country_code.clone(),
+        }),
+        NormalizerConfig::Address => Arc::new(Address),
+    }
+}
+
+/// Normalization pipelines keyed by matcher name, matchers without one keep their raw values.
+#[derive(Clone, Default)]
+pub struct NormalizerRegistry {
+    pipelines: HashMap<String, Vec<Arc<dyn Normalizer>>>,
+}
+
+impl NormalizerRegistry {
+    pub fn from_config(configs: &HashMap<String, Vec<NormalizerConfig>>) -> Self {
+        let pipelines = configs
+            .iter()
+            .map(|(matcher, steps)| (matcher.clone(), steps.iter().map(build_normalizer).collect()))
+            .collect();
+        Self { pipelines }
+    }
+
+    /// Appends a normalizer to the pipeline of `matcher`.
+    pub fn register(&mut self, matcher: &str, normalizer: Arc<dyn Normalizer>) {
+        self.pipelines.entry(matcher.to_string()).or_default().push(normalizer);
+    }
+
+    pub fn normalize(&self, matcher: &str, value: &str) -> String {
+        match self.pipelines.get(matcher) {
+            Some(pipeline) => pipeline
+                .iter()
+                .fold(value.to_string(), |value, normalizer| normalizer.normalize(&value)),
+            None => value.to_string(),
+        }
+    }
+
+    /// Normalizes the field values, keeping the raw value of the ones that changed. Fields left
+    /// empty by normalization are dropped.
+    pub fn normalize_fields(&self, fields: Vec<MatchingField>) -> Vec<MatchingField> {
+        fields
+            .into_iter()
+            .filter_map(|mut field| {
+                let normalized = self.normalize(&field.matcher, &field.value);
+                if normalized.is_empty() {
+                    return None;
+                }
+                if normalized != field.value {
+                    field.raw_value = Some(std::mem::replace(&mut field.value, normalized));
+                }
+                Some(field)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn email_strips_gmail_dots_and_tags_only() {
+        assert_eq!(Email.normalize(" John.Doe+shop@Gmail.com "), "johndoe@gmail.com");
+        assert_eq!(Email.normalize("john.doe@googlemail.com"), "johndoe@gmail.com");
+        assert_eq!(Email.normalize("John.Doe+shop@Example.com"), "john.doe+shop@example.com");
+    }
+
+    #[test]
+    fn phone_is_formatted_as_e164() {
+        let phone = Phone { default_country_code: Some("49".to_string()) };
+        assert_eq!(phone.normalize("+49 (30) 123-456"), "+4930123456");
+        assert_eq!(phone.normalize("0049 30 123456"), "+4930123456");
+        assert_eq!(phone.normalize("030 123456"), "+4930123456");
+        assert_eq!(Phone { default_country_code: None }.normalize("030 123456"), "030123456");
+    }
+
+    #[test]
+    fn phone_without_digits_is_empty() {
+        let phone = Phone { default_country_code: Some("49".to_string()) };
+        assert_eq!(phone.normalize("n/a"), "");
+        assert_eq!(phone.normalize(" - "), "");
+        assert_eq!(phone.normalize("+"), "");
+        assert_eq!(phone.normalize("0"), "");
+    }
+
+    #[test]
+    fn address_is_folded_and_abbreviated() {
+        assert_eq!(Address.normalize("Hauptstraße 5, Köln"), "hauptstr 5 koln");
+        assert_eq!(Address.normalize("12  Baker Street, Apt. 3"), "12 baker st apt 3");
+    }
+
+    #[test]
+    fn address_suffixes_need_a_stem() {
+        assert_eq!(Address.normalize("Marktplatz 1"), "marktpl 1");
+        assert_eq!(Address.normalize("Am Platz 1"), "am platz 1");
+        assert_eq!(Address.normalize("Strasse des 17. Juni"), "strasse des 17 juni");
+        assert_eq!(Address.normalize("Marktplatzweg 2"), "marktplatzweg 2");
+    }
+
+    #[test]
+    fn registry_keeps_raw_values_and_drops_empty_fields() {
+        let mut configs = HashMap::new();
+        configs.insert(
+            "customer.name".to_string(),
+            vec![NormalizerConfig::UnicodeFold, NormalizerConfig::Trim, NormalizerConfig::Lowercase],
+        );
+        let registry = NormalizerRegistry::from_config(&configs);
+
+        let fields = registry.normalize_fields(vec![
+            MatchingField::new_simple("customer.name".to_string(), " José  Müller ".to_string()),
+            MatchingField::new_simple("customer.name".to_string(), "   ".to_string()),
+            MatchingField::new_simple("device.id".to_string(), "Device-1".to_string()),
+        ]);
+
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].value, "jose muller");
+        assert_eq!(fields[0].raw_value.as_deref(), Some(" José  Müller "));
+        assert_eq!(fields[1].value, "Device-1");
+        assert_eq!(fields[1].raw_value, None);
+    }
+}
@@ -1,22 +1,33 @@
 use crate::{
-    model::{ConnectedTransaction, DirectConnection, Feature, Processible, ProcessibleSerde}, 
-    queue::{ProdQueue, QueueName, QueueService}, 
+    error::{Error, Result},
+    model::{ConnectedTransaction, DirectConnection, Feature, NeighborSummary, Neighborhood, Processible, ProcessibleSerde}, 
+    fuzzy::FuzzyMatcherRegistry,
+    geo::GeoMatcherRegistry,
+    normalizers::NormalizerRegistry,
+    queue::{
+        DeadLetter, DeadLetterService, ProdQueue, QueueMessage, QueueName, QueueService,
+        retry_backoff_seconds,
+    }, 
     storage::{CommonStorage, mongo_common::MongoCommonStorage},
-    scorers::Scorer, 
+    scorers::{Scorer, ScorerSet},
 };
-use common::config::{CommonConfig, ProcessorConfig};
+use common::config::{CommonConfig, ProcessorConfig, RecalcFanoutConfig, TraversalConfig};
+use chrono::{NaiveDateTime, Utc};
+use futures::FutureExt;
+use futures::stream::{self, StreamExt};
 use metrics::{counter, histogram, Histogram, Counter};
 use mongodb::bson::oid::ObjectId;
 use std::time::Instant;
-use std::{error::Error, marker::PhantomData, sync::Arc, time::Duration};
-use tokio::time::sleep;
+use std::{any::Any, collections::HashMap, marker::PhantomData, panic::AssertUnwindSafe, sync::{Arc, Mutex}, time::Duration};
+use tokio::{sync::watch, time::sleep};
 #[cfg(not(test))]
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 #[cfg(test)]
-use {println as debug, println as info, println as error};
+use {println as debug, println as info, println as error, println as warn};
 
 pub struct ProcessorMetrics {
     processed: Counter,
+    processing_failed: Counter,
     processing_fetch_transaction_timing: Histogram,
     processing_extract_matchers_timing: Histogram,
     processing_save_matching_timing: Histogram,
@@ -29,6 +40,7 @@ pub struct ProcessorMetrics {
     processing_total_timing: Histogram,
 
     recalculated: Counter,
+    recalculation_failed: Counter,
     recalc_fetch_transaction_timing: Histogram,
     recalc_fetch_features_simple_timing: Histogram,
     recalc_extract_features_graph_timing: Histogram,
@@ -37,6 +49,16 @@ pub struct ProcessorMetrics {
     recalc_fetch_connected_timing: Histogram,
     recalc_fetch_direct_timing: Histogram,
     recalc_total_timing: Histogram,
+
+    recalc_suppressed_cutoff: Counter,
+    recalc_suppressed_age: Counter,
+    recalc_suppressed_labeled: Counter,
+    recalc_suppressed_limit: Counter,
+
+    dead_lettered: Counter,
+    reconciled: Counter,
+    channels_reloaded: Counter,
+    shadow_scoring_failed: Counter,
 }
 
 impl ProcessorMetrics {
@@ -45,6 +67,7 @@ impl ProcessorMetrics {
         let recalculation_timing_metric_name = "frida_recalculation_timing";
         Self {
             processed: counter!("frida_processed_count", "status" => "ok"),
+            processing_failed: counter!("frida_processed_count", "status" => "error"),
             processing_fetch_transaction_timing: histogram!(processing_timing_metric_name, "stage" => "fetch_transaction"),
             processing_extract_matchers_timing: histogram!(processing_timing_metric_name, "stage" => "extract_matchers"),
             processing_save_matching_timing: histogram!(processing_timing_metric_name, "stage" => "save_matching"),
@@ -57,6 +80,7 @@ impl ProcessorMetrics {
             processing_total_timing: histogram!(processing_timing_metric_name, "stage" => "process_total"),
 
             recalculated: counter!("frida_recalculated_count", "status" => "ok"),
+            recalculation_failed: counter!("frida_recalculated_count", "status" => "error"),
             recalc_fetch_transaction_timing: histogram!(recalculation_timing_metric_name, "stage" => "fetch_transaction"),
             recalc_fetch_features_simple_timing: histogram!(recalculation_timing_metric_name, "stage" => "fetch_features_simple"),
             recalc_extract_features_graph_timing: histogram!(recalculation_timing_metric_name, "stage" => "extract_features_graph"),
@@ -65,35 +89,57 @@ impl ProcessorMetrics {
             recalc_fetch_connected_timing: histogram!(recalculation_timing_metric_name, "stage" => "fetch_connected"),
             recalc_fetch_direct_timing: histogram!(recalculation_timing_metric_name, "stage" => "fetch_direct"),
             recalc_total_timing: histogram!(recalculation_timing_metric_name, "stage" => "recalc_total"),
+
+            recalc_suppressed_cutoff: counter!("frida_recalc_suppressed_count", "reason" => "cutoff"),
+            recalc_suppressed_age: counter!("frida_recalc_suppressed_count", "reason" => "age"),
+            recalc_suppressed_labeled: counter!("frida_recalc_suppressed_count", "reason" => "labeled"),
+            recalc_suppressed_limit: counter!("frida_recalc_suppressed_count", "reason" => "limit"),
+
+            dead_lettered: counter!("frida_dead_lettered_count"),
+            reconciled: counter!("frida_reconciled_count"),
+            channels_reloaded: counter!("frida_channels_reloaded_count"),
+            shadow_scoring_failed: counter!("frida_shadow_scoring_failed_count"),
         }
     }
 }
 
 pub struct Processor<P: Processible + ProcessibleSerde<Id = ObjectId>> {
     config: Arc<ProcessorConfig>,
-    scorers: Vec<Arc<dyn Scorer>>,
+    scorers: ScorerSet,
     storage: Arc<dyn CommonStorage<P::Id>>,
     proc_queue: Arc<dyn QueueService<P::Id>>,
     recalc_queue: Arc<dyn QueueService<P::Id>>,
+    dead_letter_queue: Arc<dyn DeadLetterService<P::Id>>,
     metrics: Arc<ProcessorMetrics>,
+    normalizers: NormalizerRegistry,
+    fuzzy_matchers: FuzzyMatcherRegistry,
+    /// Last id checked by reconciliation, the next run continues after it.
+    reconciliation_cursor: Mutex<Option<P::Id>>,
     _phantom: PhantomData<P>,
 }
 
 impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
     pub fn new_raw(
         config: Arc<ProcessorConfig>,
-        scorers: Vec<Arc<dyn Scorer>>,
+        scorers: ScorerSet,
         storage: Arc<dyn CommonStorage<P::Id>>,
         proc_queue: Arc<dyn QueueService<P::Id>>,
         recalc_queue: Arc<dyn QueueService<P::Id>>,
+        dead_letter_queue: Arc<dyn DeadLetterService<P::Id>>,
     ) -> Self {
+        let normalizers = NormalizerRegistry::from_config(&config.matcher_normalizers);
+        let fuzzy_matchers = FuzzyMatcherRegistry::from_config(&config.fuzzy_matchers);
         Self {
             config,
             scorers,
             storage,
             proc_queue,
             recalc_queue,
+            dead_letter_queue,
             metrics: Arc::new(ProcessorMetrics::new()),
+            normalizers,
+            fuzzy_matchers,
+            reconciliation_cursor: Mutex::new(None),
             _phantom: PhantomData,
         }
     }
@@ -101,8 +147,8 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
     pub async fn new(
         common_config: CommonConfig,
         processing_config: Arc<ProcessorConfig>,
-        scorers: Vec<Arc<dyn Scorer>>,
-    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        scorers: ScorerSet,
+    ) -> Result<Self> {
         info!("Initializing new Processor");
 
         let matcher_configs = if let Some(configs) = &processing_config.matcher_configs {
@@ -110,15 +156,33 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
         } else {
             std::collections::HashMap::new()
         };
+        let fuzzy_matchers = FuzzyMatcherRegistry::from_config(&processing_config.fuzzy_matchers);
+        let geo_matchers = GeoMatcherRegistry::from_config(&processing_config.geo_matchers);
         let common_storage: Arc<dyn CommonStorage<P::Id>> = if !matcher_configs.is_empty() {
             Arc::new(MongoCommonStorage::with_configs(&common_config.database_url, "frida", matcher_configs)
-                .await?)
+                .await?
+                .with_hub_config(processing_config.hubs.clone())
+                .with_match_node_storage_config(processing_config.match_node_storage.clone())
+                .with_fuzzy_matchers(fuzzy_matchers.clone())
+                .with_geo_matchers(geo_matchers.clone()))
         } else {
-            Arc::new(MongoCommonStorage::new(&common_config.database_url, "frida").await?)
+            Arc::new(MongoCommonStorage::new(&common_config.database_url, "frida")
+                .await?
+                .with_hub_config(processing_config.hubs.clone())
+                .with_match_node_storage_config(processing_config.match_node_storage.clone())
+                .with_fuzzy_matchers(fuzzy_matchers.clone())
+                .with_geo_matchers(geo_matchers.clone()))
         };
 
-        let proc_queue: Arc<dyn QueueService<P::Id>> = Arc::new(ProdQueue::new(&common_config, QueueName::Processing).await?);
-        let recalc_queue = Arc::new(ProdQueue::new(&common_config, QueueName::Recalculation).await?);
+        let visibility_timeout = processing_config.visibility_timeout_seconds;
+        let proc_queue: Arc<dyn QueueService<P::Id>> = Arc::new(
+            ProdQueue::with_visibility_timeout(&common_config, QueueName::Processing, visibility_timeout).await?,
+        );
+        let recalc_queue = Arc::new(
+            ProdQueue::with_visibility_timeout(&common_config, QueueName::Recalculation, visibility_timeout).await?,
+        );
+        let dead_letter_queue = Arc::new(ProdQueue::new(&common_config, QueueName::DeadLetter).await?);
+        let normalizers = NormalizerRegistry::from_config(&processing_config.matcher_normalizers);
 
         Ok(Self {
             config: processing_config,
@@ -126,31 +190,234 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
             storage: common_storage,
             proc_queue,
             recalc_queue,
+            dead_letter_queue,
             metrics: Arc::new(ProcessorMetrics::new()),
+            normalizers,
+            fuzzy_matchers,
+            reconciliation_cursor: Mutex::new(None),
             _phantom: PhantomData,
         })
     }
 
-    pub async fn start_processing_worker(self: Arc<Processor<P>>) -> Result<(), Box<dyn Error + Send + Sync>> {
+    /// Polls the queues until `shutdown` flips to `true`. A batch that is already dequeued is
+    /// always finished before the worker returns.
+    pub async fn start_processing_worker(
+        self: Arc<Processor<P>>,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> Result<()> {
         info!("Starting processing worker");
+        let batch_size = self.config.batch_size.max(1);
+
+        while !*shutdown.borrow() {
+            let batch = self.proc_queue.fetch_next(batch_size).await?;
+            if !batch.is_empty() {
+                self.handle_batch(QueueName::Processing, &self.proc_queue, batch).await;
+                continue;
+            }
+
+            let batch = self.recalc_queue.fetch_next(batch_size).await?;
+            if !batch.is_empty() {
+                self.handle_batch(QueueName::Recalculation, &self.recalc_queue, batch).await;
+                continue;
+            }
+
+            tokio::select! {
+                _ = sleep(Duration::from_millis(self.config.sleep_ms)) => {}
+                _ = shutdown.changed() => {}
+            }
+        }
+
+        info!("Processing worker stopped");
+        Ok(())
+    }
+
+    /// Periodically re-enqueues transactions that were stored but never reached the processing
+    /// queue, e.g. because the importer crashed between the Mongo insert and the enqueue.
+    pub async fn start_reconciliation_worker(
+        self: Arc<Processor<P>>,
+        mut shutdown: watch::Receiver<bool>,
+    ) {
+        info!("Starting reconciliation worker");
+        let interval = Duration::from_secs(self.config.reconciliation_interval_seconds.max(1));
+
+        while !*shutdown.borrow() {
+            if let Err(e) = self.reconcile_unqueued().await {
+                error!("Reconciliation failed: {}", e);
+            }
+
+            tokio::select! {
+                _ = sleep(interval) => {}
+                _ = shutdown.changed() => {}
+            }
+        }
+
+        info!("Reconciliation worker stopped");
+    }
+
+    /// Periodically picks up activated, deactivated and edited scoring channels, so they take
+    /// effect without restarting the processor.
+    pub async fn start_channel_reload_worker(
+        self: Arc<Processor<P>>,
+        mut shutdown: watch::Receiver<bool>,
+    ) {
+        info!("Starting channel reload worker");
+        let interval = Duration::from_secs(self.config.channel_reload_interval_seconds.max(1));
 
         loop {
-            if let Some((transaction_id, msg_id)) = self.proc_queue.fetch_next(1).await?.first() {
-                self.process(*transaction_id).await?;
-                self.proc_queue.mark_processed(*msg_id).await?;
-            } else if let Some((transaction_id, msg_id)) = self.recalc_queue.fetch_next(1).await?.first() {
-                self.recalculate(*transaction_id).await?;
-                self.recalc_queue.mark_processed(*msg_id).await?;
-            } else {
-                sleep(Duration::from_millis(self.config.sleep_ms)).await;
+            tokio::select! {
+                _ = sleep(interval) => {}
+                _ = shutdown.changed() => {}
+            }
+            if *shutdown.borrow() {
+                break;
+            }
+
+            if let Err(e) = self.reload_channels().await {
+                error!("Channel reload failed: {}", e);
             }
         }
+
+        info!("Channel reload worker stopped");
+    }
+
+    /// Rebuilds the scorers if the active channels changed. Channels that can't be applied are
+    /// logged and keep their previous scorer. Returns whether the scorers were replaced.
+    pub async fn reload_channels(&self) -> Result<bool> {
+        let active = self.storage.get_active_model_activations().await?;
+        let reload = self.scorers.reload(&active).await;
+        for failure in &reload.failures {
+            error!("Channel reload failed, keeping its current scorer: {}", failure);
+        }
+        let reloaded = reload.replaced;
+        if reloaded {
+            self.metrics.channels_reloaded.increment(1);
+            info!(
+                "Reloaded scoring channels: {:?}",
+                active.iter().map(|c| format!("{} v{}", c.channel_name, c.version)).collect::<Vec<_>>()
+            );
+        }
+        Ok(reloaded)
+    }
+
+    /// Enqueues unprocessed transactions older than the grace period that are neither in the
+    /// processing queue nor dead-lettered. Each run checks the next page of unprocessed
+    /// transactions and starts over after the last one, so queued and dead-lettered ids can't
+    /// hide the ones that were lost. Returns the number of enqueued transactions.
+    pub async fn reconcile_unqueued(&self) -> Result<usize> {
+        let created_before = Utc::now().naive_utc()
+            - chrono::Duration::seconds(self.config.reconciliation_grace_seconds.max(0));
+        let batch_size = self.config.reconciliation_batch_size.max(1);
+        let after = *self.reconciliation_cursor.lock().unwrap_or_else(|e| e.into_inner());
+        let ids = self
+            .storage
+            .find_unprocessed_transaction_ids(created_before, after, batch_size)
+            .await?;
+        let next = if (ids.len() as i64) < batch_size { None } else { ids.last().copied() };
+        *self.reconciliation_cursor.lock().unwrap_or_else(|e| e.into_inner()) = next;
+        if ids.is_empty() {
+            return Ok(0);
+        }
+
+        let enqueued_ids = self.proc_queue.is_enqueued(&ids).await?;
+        let dead_lettered_ids = self.dead_letter_queue.is_dead_lettered(&ids).await?;
+        let missing_ids: Vec<P::Id> = ids
+            .into_iter()
+            .filter(|id| !enqueued_ids.contains(id) && !dead_lettered_ids.contains(id))
+            .collect();
+        if missing_ids.is_empty() {
+            return Ok(0);
+        }
+
+        self.proc_queue.enqueue(&missing_ids).await?;
+        self.metrics.reconciled.increment(missing_ids.len() as u64);
+        info!("Reconciliation: enqueued {} unqueued transactions", missing_ids.len());
+        Ok(missing_ids.len())
+    }
+
+    /// Runs a dequeued batch with bounded concurrency. Each message is archived on its own
+    /// success, retried with backoff on failure and dead-lettered once retries are exhausted.
+    async fn handle_batch(
+        &self,
+        queue_name: QueueName,
+        queue: &Arc<dyn QueueService<P::Id>>,
+        batch: Vec<QueueMessage<P::Id>>,
+    ) {
+        debug!("Handling batch of {} messages from {}", batch.len(), queue_name);
+
+        stream::iter(batch)
+            .for_each_concurrent(self.config.batch_concurrency.max(1), |msg| async move {
+                let handling = async {
+                    match queue_name {
+                        QueueName::Recalculation => self.recalculate(msg.message).await,
+                        _ => self.process(msg.message).await,
+                    }
+                };
+                // A panic must not take down the worker together with the rest of the batch
+                let result = AssertUnwindSafe(handling)
+                    .catch_unwind()
+                    .await
+                    .unwrap_or_else(|panic| Err(Error::Panic(panic_message(panic))));
+
+                let outcome = match result {
+                    Ok(()) => queue.mark_processed(msg.msg_id).await,
+                    Err(e) => {
+                        match queue_name {
+                            QueueName::Recalculation => self.metrics.recalculation_failed.increment(1),
+                            _ => self.metrics.processing_failed.increment(1),
+                        }
+                        self.handle_failure(queue_name, queue, &msg, e).await
+                    }
+                };
+
+                if let Err(e) = outcome {
+                    error!("Failed to settle message {} from {}: {}", msg.msg_id, queue_name, e);
+                }
+            })
+            .await;
+    }
+
+    async fn handle_failure(
+        &self,
+        queue_name: QueueName,
+        queue: &Arc<dyn QueueService<P::Id>>,
+        msg: &QueueMessage<P::Id>,
+        error: Error,
+    ) -> Result<()> {
+        // Retrying cannot fix a bad payload or missing configuration, so those skip the backoff
+        if !error.is_retryable() || msg.read_count >= self.config.max_retries {
+            error!(
+                "Dead-lettering transaction {} from {} after {} attempts: {}",
+                msg.message, queue_name, msg.read_count, error
+            );
+            self.dead_letter_queue
+                .dead_letter(DeadLetter {
+                    transaction_id: msg.message,
+                    source_queue: queue_name,
+                    error: error.to_string(),
+                    read_count: msg.read_count,
+                    failed_at: Utc::now(),
+                })
+                .await?;
+            self.metrics.dead_lettered.increment(1);
+            return queue.mark_processed(msg.msg_id).await;
+        }
+
+        let delay = retry_backoff_seconds(
+            msg.read_count,
+            self.config.retry_backoff_base_seconds,
+            self.config.retry_backoff_max_seconds,
+        );
+        error!(
+            "Failed to handle transaction {} from {} (attempt {}), retrying in {}s: {}",
+            msg.message, queue_name, msg.read_count, delay, error
+        );
+        queue.retry_later(msg.msg_id, delay).await
     }
 
     pub async fn process(
         &self,
         transaction_id: P::Id,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    ) -> Result<()> {
         info!(
             "Processing: Starting, transaction id: {:?}",
             &transaction_id
@@ -158,9 +425,10 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
 
         let perf_timer = Instant::now();
         let transaction = self.storage.get_transaction(transaction_id).await?;
+        let created_at = transaction.order_time();
 
         let processible: P = P::from_json(transaction.payload)
-            .expect("Processing: Failed to deserialize transaction during processing");
+            .map_err(Error::Deserialization)?;
 
         debug!(
             "Processing: Extracting matching fields for transaction {:?}",
@@ -169,40 +437,45 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
         let perf_stage_1 = perf_timer.elapsed();
         self.metrics.processing_fetch_transaction_timing.record(perf_stage_1);
 
-        let matching_fields = processible.extract_matching_fields();
+        let mut matching_fields = self.normalizers.normalize_fields(processible.extract_matching_fields());
+        matching_fields.extend(self.fuzzy_matchers.derive_fields(&matching_fields));
         let perf_stage_2 = perf_timer.elapsed();
         self.metrics.processing_extract_matchers_timing.record(perf_stage_2 - perf_stage_1);
 
         self.storage
             .save_matching_fields(&transaction_id, &matching_fields)
-            .await
-            .expect("Processing: Failed to save matching fields during processing");
+            .await?;
+        let unlinked_transactions = self.storage
+            .retire_stale_matching_fields(&transaction_id)
+            .await?;
         let perf_stage_3 = perf_timer.elapsed();
         self.metrics.processing_save_matching_timing.record(perf_stage_3 - perf_stage_2);
 
         // Fetch connected transactions and direct connections
-        let connected_transactions: Vec<ConnectedTransaction> = self.fetch_connected_transactions(&processible.payload_number()).await?;
+        let mut connected_transactions: Vec<ConnectedTransaction> = self.fetch_connected_transactions(&processible.payload_number(), &self.config.process_traversal).await?;
         let perf_stage_4 = perf_timer.elapsed();
         self.metrics.processing_fetch_connected_timing.record(perf_stage_4 - perf_stage_3);
 
-        let direct_connections: Vec<DirectConnection> = self.fetch_direct_connections(&processible.payload_number()).await?;
+        let mut direct_connections: Vec<DirectConnection> = self.fetch_direct_connections(&processible.payload_number()).await?;
         let perf_stage_5 = perf_timer.elapsed();
         self.metrics.processing_fetch_direct_timing.record(perf_stage_5 - perf_stage_4);
 
-        // Enqueue connected transactions for recalculation
-        let ids = connected_transactions.iter().map(|ct| ct.payload_number.clone()).collect::<Vec<_>>();
-        // TODO: get tx ids or operate on payload numbers
-        let enqueued_ids = self.recalc_queue.is_enqueued(&ids).await?;
-        let not_enqueued_ids: Vec<P::Id> = ids.into_iter().filter(|id| !enqueued_ids.contains(id)).collect();
-        self.recalc_queue.enqueue(&not_enqueued_ids).await?;
-        debug!("Transactions added to the recalculation queue: {}", not_enqueued_ids.len());
+        // Neighbors that lost their link to a previous version need new graph features as well
+        let recalc_candidates: Vec<ConnectedTransaction> = connected_transactions
+            .iter()
+            .chain(unlinked_transactions.iter())
+            .cloned()
+            .collect();
+        self.enqueue_neighbors_for_recalc(&recalc_candidates).await?;
 
         let simple_features = processible.extract_simple_features();
         let perf_stage_6 = perf_timer.elapsed();
         self.metrics.processing_extract_features_simple_timing.record(perf_stage_6 - perf_stage_5);
 
+        let neighborhood = self.fetch_neighborhood(created_at, &connected_transactions, &direct_connections).await?;
+        neighborhood.label_connections(&mut connected_transactions, &mut direct_connections);
         let graph_features =
-            processible.extract_graph_features(&connected_transactions, &direct_connections);
+            processible.extract_graph_features(&connected_transactions, &direct_connections, &neighborhood);
         let perf_stage_7 = perf_timer.elapsed();
         self.metrics.processing_extract_features_graph_timing.record(perf_stage_7 - perf_stage_6);
         debug!(
@@ -223,9 +496,7 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
         let perf_stage_8 = perf_timer.elapsed();
         self.metrics.processing_save_features_timing.record(perf_stage_8 - perf_stage_7);
 
-        for scorer in self.scorers.iter() {
-            self.score_and_save_results(transaction_id, scorer.clone(), &simple_features, &graph_features).await?;
-        }
+        self.score_with_all_channels(transaction_id, &simple_features, &graph_features).await?;
 
         let perf_stage_9 = perf_timer.elapsed();
         self.metrics.processing_score_and_save_timing.record(perf_stage_9 - perf_stage_8);
@@ -245,31 +516,33 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
     pub async fn recalculate(
         &self,
         transaction_id: P::Id,
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    ) -> Result<()> {
         info!("Recalculation: Starting transaction id: {:?}", &transaction_id);
 
         let perf_timer = Instant::now();
         let transaction = self.storage.get_transaction(transaction_id).await?;
+        let created_at = transaction.order_time();
 
-        
         let processible: P = P::from_json(transaction.payload)
-            .expect("Failed to deserialize transaction during recalculation");
+            .map_err(Error::Deserialization)?;
 
         let payload_number = processible.payload_number();
         let perf_stage_1 = perf_timer.elapsed();
         self.metrics.recalc_fetch_transaction_timing.record(perf_stage_1);
 
-        let connected_transactions: Vec<ConnectedTransaction> = self.fetch_connected_transactions(&payload_number).await?;
+        let mut connected_transactions: Vec<ConnectedTransaction> = self.fetch_connected_transactions(&payload_number, &self.config.recalc_traversal).await?;
         let perf_stage_2 = perf_timer.elapsed();
         self.metrics.recalc_fetch_connected_timing.record(perf_stage_2 - perf_stage_1);
 
-        let direct_connections: Vec<DirectConnection> = self.fetch_direct_connections(&payload_number).await?;
+        let mut direct_connections: Vec<DirectConnection> = self.fetch_direct_connections(&payload_number).await?;
         let perf_stage_3 = perf_timer.elapsed();
         self.metrics.recalc_fetch_direct_timing.record(perf_stage_3 - perf_stage_2);
 
         debug!("Extracting features for transaction {:?} in recalculation", transaction_id);
+        let neighborhood = self.fetch_neighborhood(created_at, &connected_transactions, &direct_connections).await?;
+        neighborhood.label_connections(&mut connected_transactions, &mut direct_connections);
         let features =
-            processible.extract_graph_features(&connected_transactions, &direct_connections);
+            processible.extract_graph_features(&connected_transactions, &direct_connections, &neighborhood);
         let perf_stage_4 = perf_timer.elapsed();
         self.metrics.recalc_extract_features_graph_timing.record(perf_stage_4 - perf_stage_3);
 
@@ -286,13 +559,14 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
 
         // Fetch already persisted simple features and combine with freshly computed graph features
         let transaction = self.storage.get_transaction(transaction_id).await?;
-        let features_set = transaction.features_set.expect("Failed to get features set during recalculation");
+        let features_set = transaction.features_set.ok_or_else(|| {
+            Error::NotFound(format!("features set of transaction {}", transaction_id))
+        })?;
         let perf_stage_6 = perf_timer.elapsed();
         self.metrics.recalc_fetch_features_simple_timing.record(perf_stage_6 - perf_stage_5);
 
-        for scorer in self.scorers.iter() {
-            self.score_and_save_results(transaction_id, scorer.clone(), &features_set.simple_features, &features_set.graph_features).await?;
-        }
+        self.score_with_all_channels(transaction_id, &features_set.simple_features, &features_set.graph_features)
+            .await?;
         let perf_stage_7 = perf_timer.elapsed();
         self.metrics.recalc_score_and_save_timing.record(perf_stage_7 - perf_stage_6);
 
@@ -307,18 +581,12 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
     async fn fetch_connected_transactions(
         &self,
         payload_number: &str,
-    ) -> Result<Vec<ConnectedTransaction>, Box<dyn Error + Send + Sync>> {
+        traversal: &TraversalConfig,
+    ) -> Result<Vec<ConnectedTransaction>> {
         debug!("Fetching connected transactions for payload_number {:?}", payload_number);
-        // Default options: max_depth=3, limit_count=100, no date filters, min_confidence=50
         let connected_transactions = self
             .storage
-            .find_connected_transactions(
-                payload_number,
-                Some(10),     // max_depth
-                Some(200), // limit_count
-                None,        // filter_config
-                Some(50),    // min_confidence
-            )
+            .find_connected_transactions(payload_number, traversal, Some(&self.config.graph_filter))
             .await?;
         info!(
             "Found {} connected transactions for transaction payload_number {:?}",
@@ -328,10 +596,39 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
         Ok(connected_transactions)
     }
 
+    /// Queues the latest versions of connected transactions for recalculation, within the limits
+    /// of `recalc_fanout`.
+    async fn enqueue_neighbors_for_recalc(&self, connected_transactions: &[ConnectedTransaction]) -> Result<()> {
+        let payload_numbers = connected_transactions.iter().map(|ct| ct.payload_number.clone()).collect::<Vec<_>>();
+        let neighbors = self.storage.get_latest_neighbor_summaries(&payload_numbers).await?;
+        let neighbor_ids: Vec<P::Id> = neighbors.iter().map(|n| n.id).collect();
+        let enqueued_ids = self.recalc_queue.is_enqueued(&neighbor_ids).await?;
+
+        let selection = select_recalc_neighbors(
+            connected_transactions,
+            neighbors,
+            &enqueued_ids,
+            &self.config.recalc_fanout,
+            Utc::now().naive_utc(),
+        );
+        self.recalc_queue.enqueue(&selection.ids).await?;
+
+        self.metrics.recalc_suppressed_cutoff.increment(selection.before_cutoff as u64);
+        self.metrics.recalc_suppressed_age.increment(selection.too_old as u64);
+        self.metrics.recalc_suppressed_labeled.increment(selection.labeled as u64);
+        self.metrics.recalc_suppressed_limit.increment(selection.over_limit as u64);
+        debug!(
+            "Transactions added to the recalculation queue: {}, suppressed: {}",
+            selection.ids.len(),
+            selection.suppressed()
+        );
+        Ok(())
+    }
+
     async fn fetch_direct_connections(
         &self,
         payload_number: &str,
-    ) -> Result<Vec<DirectConnection>, Box<dyn Error + Send + Sync>> {
+    ) -> Result<Vec<DirectConnection>> {
         debug!("Fetching direct connections for payload_number {:?}", &payload_number);
         let direct_connections: Vec<DirectConnection> = self.storage.get_direct_connections(payload_number).await?;
 
@@ -339,12 +636,33 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
         Ok(direct_connections)
     }
 
+    /// Timestamps and `neighbor_features` of every connected or directly connected neighbor.
+    async fn fetch_neighborhood(
+        &self,
+        created_at: NaiveDateTime,
+        connected_transactions: &[ConnectedTransaction],
+        direct_connections: &[DirectConnection],
+    ) -> Result<Neighborhood> {
+        let mut payload_numbers: Vec<String> = connected_transactions
+            .iter()
+            .map(|ct| ct.payload_number.clone())
+            .chain(direct_connections.iter().map(|dc| dc.payload_number.clone()))
+            .collect();
+        payload_numbers.sort();
+        payload_numbers.dedup();
+        let neighbors = self
+            .storage
+            .get_neighbor_snapshots(&payload_numbers, &self.config.neighbor_features)
+            .await?;
+        Ok(Neighborhood::new(created_at, neighbors))
+    }
+
     async fn save_features(
         &self,
         transaction_id: P::Id,
         simple_features: &Option<&[Feature]>,
         graph_features: &[Feature],
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    ) -> Result<()> {
         debug!("Saving features for transaction {:?}", &transaction_id);
 
         if let Err(e) = self
@@ -360,13 +678,42 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
         Ok(())
     }
 
+    /// Scores with the live channels, then with the shadow channels. Shadow failures are logged
+    /// and counted but never fail the transaction, a broken challenger must not hold back scoring.
+    async fn score_with_all_channels(
+        &self,
+        transaction_id: P::Id,
+        simple_features: &[Feature],
+        graph_features: &[Feature],
+    ) -> Result<()> {
+        let scorers = self.scorers.current();
+        let (shadow, live): (Vec<_>, Vec<_>) = scorers.iter().partition(|scorer| scorer.channel().shadow);
+        for scorer in live {
+            self.score_and_save_results(transaction_id, scorer.clone(), simple_features, graph_features).await?;
+        }
+        for scorer in shadow {
+            if let Err(e) =
+                self.score_and_save_results(transaction_id, scorer.clone(), simple_features, graph_features).await
+            {
+                self.metrics.shadow_scoring_failed.increment(1);
+                warn!(
+                    "Shadow channel {} failed to score transaction {}: {}",
+                    scorer.channel().channel_name,
+                    transaction_id,
+                    e
+                );
+            }
+        }
+        Ok(())
+    }
+
     async fn score_and_save_results(
         &self,
         transaction_id: P::Id,
         scorer: Arc<dyn Scorer>,
         simple_features: &[Feature],
         graph_features: &[Feature],
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    ) -> Result<()> {
         debug!("Scoring transaction {}", transaction_id);
 
         let scoring_result = scorer
@@ -378,3 +725,119 @@ impl<P: Processible + ProcessibleSerde<Id = ObjectId>> Processor<P> {
         Ok(())
     }
 }
+
+fn panic_message(panic: Box<dyn Any + Send>) -> String {
+    panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic payload".to_string())
+}
+
+/// Neighbors picked for recalculation and the number left out for each reason.
+#[derive(Debug, PartialEq)]
+struct RecalcSelection<ID> {
+    ids: Vec<ID>,
+    before_cutoff: usize,
+    too_old: usize,
+    labeled: usize,
+    over_limit: usize,
+}
+
+impl<ID> RecalcSelection<ID> {
+    fn suppressed(&self) -> usize {
+        self.before_cutoff + self.too_old + self.labeled + self.over_limit
+    }
+}
+
+/// Picks neighbors to recalculate, strongest connections first. Neighbors already waiting in the
+/// queue are skipped without counting as suppressed.
+fn select_recalc_neighbors<ID: PartialEq>(
+    connected_transactions: &[ConnectedTransaction],
+    mut neighbors: Vec<NeighborSummary<ID>>,
+    enqueued_ids: &[ID],
+    config: &RecalcFanoutConfig,
+    now: NaiveDateTime,
+) -> RecalcSelection<ID> {
+    let confidence: HashMap<&str, i32> = connected_transactions
+        .iter()
+        .map(|ct| (ct.payload_number.as_str(), ct.total_confidence))
+        .collect();
+    neighbors.sort_by_key(|n| std::cmp::Reverse(confidence.get(n.payload_number.as_str()).copied().unwrap_or(0)));
+
+    let cutoff = config.cutoff_date.map(|date| date.naive_utc());
+    let oldest = config.max_age_days.map(|days| now - chrono::Duration::days(days));
+    let mut selection = RecalcSelection { ids: Vec::new(), before_cutoff: 0, too_old: 0, labeled: 0, over_limit: 0 };
+
+    for neighbor in neighbors {
+        if cutoff.is_some_and(|cutoff| neighbor.created_at < cutoff) {
+            selection.before_cutoff += 1;
+        } else if oldest.is_some_and(|oldest| neighbor.created_at < oldest) {
+            selection.too_old += 1;
+        } else if config.skip_labeled && neighbor.is_labeled {
+            selection.labeled += 1;
+        } else if !enqueued_ids.contains(&neighbor.id) {
+            selection.ids.push(neighbor.id);
+        }
+    }
+
+    if let Some(max_neighbors) = config.max_neighbors {
+        selection.over_limit = selection.ids.len().saturating_sub(max_neighbors);
+        selection.ids.truncate(max_neighbors);
+    }
+    selection
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveDate, TimeZone};
+
+    fn neighbor(id: i32, created_at: NaiveDateTime, is_labeled: bool) -> NeighborSummary<i32> {
+        NeighborSummary { id, payload_number: format!("P{}", id), created_at, is_labeled }
+    }
+
+    fn connected(id: i32, total_confidence: i32) -> ConnectedTransaction {
+        ConnectedTransaction { payload_number: format!("P{}", id), path: vec![], total_confidence, label: None }
+    }
+
+    #[test]
+    fn select_recalc_neighbors_applies_filters_and_limit() {
+        let now = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let recent = now - chrono::Duration::days(1);
+        let connected = vec![connected(1, 60), connected(2, 90), connected(3, 80), connected(4, 70), connected(5, 50), connected(6, 95)];
+        let neighbors = vec![
+            neighbor(1, recent, false),
+            neighbor(2, recent, false),
+            neighbor(3, recent, true),
+            neighbor(4, now - chrono::Duration::days(40), false),
+            neighbor(5, NaiveDate::from_ymd_opt(2025, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(), false),
+            neighbor(6, recent, false),
+        ];
+        let config = RecalcFanoutConfig {
+            cutoff_date: Some(Utc.with_ymd_and_hms(2025, 6, 1, 0, 0, 0).unwrap()),
+            max_age_days: Some(30),
+            max_neighbors: Some(1),
+            skip_labeled: true,
+        };
+
+        // 6 is already queued, 2 is the strongest remaining link and 1 is cut by the limit
+        let selection = select_recalc_neighbors(&connected, neighbors, &[6], &config, now);
+        assert_eq!(
+            selection,
+            RecalcSelection { ids: vec![2], before_cutoff: 1, too_old: 1, labeled: 1, over_limit: 1 }
+        );
+        assert_eq!(selection.suppressed(), 4);
+    }
+
+    #[test]
+    fn select_recalc_neighbors_keeps_everything_by_default() {
+        let now = NaiveDate::from_ymd_opt(2026, 3, 1).unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let connected = vec![connected(1, 60), connected(2, 90)];
+        let neighbors = vec![neighbor(1, now, true), neighbor(2, now, false)];
+
+        let selection = select_recalc_neighbors(&connected, neighbors, &[], &RecalcFanoutConfig::default(), now);
+        assert_eq!(selection.ids, vec![2, 1]);
+        assert_eq!(selection.suppressed(), 0);
+    }
+}
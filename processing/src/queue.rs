@@ -1,110 +1,281 @@
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use common::config::CommonConfig;
 use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend, Statement};
-use serde::{Serialize, de::DeserializeOwned};
-use std::{error::Error, str::FromStr};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use pgmq::{Message, PGMQueue};
 use strum_macros::Display;
 
+use crate::error::{Error, Result};
+
+/// A message read from a queue together with its delivery metadata.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QueueMessage<T> {
+    pub msg_id: i64,
+    /// Number of times the message has been read, including the current read.
+    pub read_count: i32,
+    pub message: T,
+}
+
 // Queue service interface
 #[async_trait]
 pub trait QueueService<ID: Send + Sync + Serialize + DeserializeOwned>: Send + Sync + 'static {
-    async fn fetch_next(&self, number: i32) -> Result<Vec<(ID, i64)>, Box<dyn Error + Send + Sync>>;
-    async fn mark_processed(&self, id: i64) -> Result<(), Box<dyn Error + Send + Sync>>;
-    async fn enqueue(&self, ids: &[ID]) -> Result<(), Box<dyn Error + Send + Sync>>;
-    async fn is_enqueued(&self, ids: &[ID]) -> Result<Vec<ID>, Box<dyn Error + Send + Sync>>;
+    async fn fetch_next(&self, number: i32) -> Result<Vec<QueueMessage<ID>>>;
+    async fn mark_processed(&self, id: i64) -> Result<()>;
+    /// Hides the message for `delay_seconds` so it is retried later.
+    async fn retry_later(&self, id: i64, delay_seconds: i32) -> Result<()>;
+    async fn enqueue(&self, ids: &[ID]) -> Result<()>;
+    async fn is_enqueued(&self, ids: &[ID]) -> Result<Vec<ID>>;
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display)]
+/// A message that exhausted its retries, kept together with the failure reason.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeadLetter<ID> {
+    pub transaction_id: ID,
+    pub source_queue: QueueName,
+    pub error: String,
+    pub read_count: i32,
+    pub failed_at: DateTime<Utc>,
+}
+
+// Dead letter queue interface
+#[async_trait]
+pub trait DeadLetterService<ID: Send + Sync + Serialize + DeserializeOwned>: Send + Sync + 'static {
+    async fn dead_letter(&self, letter: DeadLetter<ID>) -> Result<()>;
+    async fn list_dead_letters(&self, limit: i64) -> Result<Vec<QueueMessage<DeadLetter<ID>>>>;
+    /// Moves the given dead letters back to their source queues, returns the number requeued.
+    async fn requeue(&self, msg_ids: &[i64]) -> Result<usize>;
+    /// Returns those of `ids` that have a dead letter waiting for inspection.
+    async fn is_dead_lettered(&self, ids: &[ID]) -> Result<Vec<ID>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display, Serialize, Deserialize)]
 pub enum QueueName {
     #[strum(to_string = "processing_queue")]
     Processing,
     #[strum(to_string = "recalculation_queue")]
     Recalculation,
+    #[strum(to_string = "dead_letter_queue")]
+    DeadLetter,
+}
+
+/// Exponential backoff for the `read_count`-th failed attempt, capped at `max_seconds`.
+pub fn retry_backoff_seconds(read_count: i32, base_seconds: i32, max_seconds: i32) -> i32 {
+    let exponent = (read_count - 1).clamp(0, 30) as u32;
+    base_seconds
+        .max(1)
+        .saturating_mul(2_i32.saturating_pow(exponent))
+        .min(max_seconds.max(1))
 }
 
 pub struct ProdQueue {
     queue: PGMQueue,
     queue_name: QueueName,
     db: DatabaseConnection,
+    visibility_timeout_seconds: i32,
 }
 
 impl ProdQueue {
-    pub async fn new(config: &CommonConfig, queue_name: QueueName) -> Result<Self, Box<dyn Error + Send + Sync>> {
+    pub async fn new(config: &CommonConfig, queue_name: QueueName) -> Result<Self> {
+        Self::with_visibility_timeout(config, queue_name, 30).await
+    }
+
+    pub async fn with_visibility_timeout(
+        config: &CommonConfig,
+        queue_name: QueueName,
+        visibility_timeout_seconds: i32,
+    ) -> Result<Self> {
         println!("Trying to connect to db for queue: {:?}", queue_name);
-        let db = Database::connect(config.database_url.clone())
-            .await
-            .expect("Failed to connect to database");
+        let db = Database::connect(config.database_url.clone()).await?;
 
         let index_sql = format!("create index if not exists idx_q_{}_message on pgmq.q_{}(message)",
             queue_name.to_string().to_lowercase(), queue_name.to_string().to_lowercase());
         db.execute(Statement::from_string(DbBackend::Postgres, index_sql)).await?;
 
-        let queue: PGMQueue = PGMQueue::new(config.database_url.clone())
-            .await
-            .expect("Failed to connect to postgres");
+        let queue: PGMQueue = PGMQueue::new(config.database_url.clone()).await?;
         println!("Connected to db for prod_queue");
 
         println!("Creating a queue '{:?}'", queue_name);
-        queue.create(&queue_name.to_string())
-            .await
-            .expect("Failed to create queue");
-        Ok(Self { queue, queue_name, db})
+        queue.create(&queue_name.to_string()).await?;
+        Ok(Self { queue, queue_name, db, visibility_timeout_seconds })
     }
 }
 
 #[async_trait]
-impl<ID: Send + Sync + Serialize + DeserializeOwned + ToString + FromStr> QueueService<ID> for ProdQueue {
-    async fn enqueue(&self, ids: &[ID]) -> Result<(), Box<dyn Error + Send + Sync>> {
+impl<ID: Send + Sync + Serialize + DeserializeOwned> QueueService<ID> for ProdQueue {
+    async fn enqueue(&self, ids: &[ID]) -> Result<()> {
         if ids.is_empty() {
             return Ok(());
         }
 
-        self.queue.send_batch::<ID>(&self.queue_name.to_string(), &ids)
-            .await
-            .expect("Failed to send message to queue");
+        self.queue.send_batch::<ID>(&self.queue_name.to_string(), &ids).await?;
         Ok(())
     }
 
-    async fn fetch_next(&self, _number: i32) -> Result<Vec<(ID, i64)>, Box<dyn Error + Send + Sync>> {
-        let visibility_timeout_seconds: i32 = 30;
-
-        let received_message: Option<Message<ID>> = self
+    async fn fetch_next(&self, number: i32) -> Result<Vec<QueueMessage<ID>>> {
+        let received_messages: Option<Vec<Message<ID>>> = self
             .queue
-            .read::<ID>(&self.queue_name.to_string(), Some(visibility_timeout_seconds))
+            .read_batch::<ID>(&self.queue_name.to_string(), Some(self.visibility_timeout_seconds), number.max(1))
             .await?;
 
-        Ok(received_message.map(|msg| vec![(msg.message, msg.msg_id)]).unwrap_or_default())
+        Ok(received_messages
+            .map(|msgs| {
+                msgs.into_iter()
+                    .map(|msg| QueueMessage {
+                        msg_id: msg.msg_id,
+                        read_count: msg.read_ct,
+                        message: msg.message,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default())
     }
 
-    async fn mark_processed(&self, msg_id: i64) -> Result<(), Box<dyn Error + Send + Sync>> {
-        let _ = self.queue.archive(&self.queue_name.to_string(), msg_id)
-            .await
-            .expect("Failed to archive message");
+    async fn retry_later(&self, msg_id: i64, delay_seconds: i32) -> Result<()> {
+        let vt = Utc::now() + chrono::Duration::seconds(delay_seconds as i64);
+        self.queue
+            .set_vt::<serde_json::Value>(&self.queue_name.to_string(), msg_id, vt)
+            .await?;
+        Ok(())
+    }
+
+    async fn mark_processed(&self, msg_id: i64) -> Result<()> {
+        self.queue.archive(&self.queue_name.to_string(), msg_id).await?;
         Ok(())
     }
 
-    async fn is_enqueued(&self, ids: &[ID]) -> Result<Vec<ID>, Box<dyn Error + Send + Sync>> {
+    async fn is_enqueued(&self, ids: &[ID]) -> Result<Vec<ID>> {
+        self.select_present_ids("message", ids).await
+    }
+}
+
+#[async_trait]
+impl<ID: Send + Sync + Serialize + DeserializeOwned + 'static> DeadLetterService<ID> for ProdQueue {
+    async fn dead_letter(&self, letter: DeadLetter<ID>) -> Result<()> {
+        self.queue.send(&self.queue_name.to_string(), &letter).await?;
+        Ok(())
+    }
+
+    async fn list_dead_letters(&self, limit: i64) -> Result<Vec<QueueMessage<DeadLetter<ID>>>> {
+        let sql = format!(
+            "select msg_id, read_ct, message from pgmq.q_{} order by msg_id limit {};",
+            self.queue_name, limit.max(0)
+        );
+        self.query_dead_letters(sql).await
+    }
+
+    async fn requeue(&self, msg_ids: &[i64]) -> Result<usize> {
+        if msg_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let ids_str = msg_ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(",");
+        let sql = format!(
+            "select msg_id, read_ct, message from pgmq.q_{} where msg_id in ({});",
+            self.queue_name, ids_str
+        );
+        let letters: Vec<QueueMessage<DeadLetter<ID>>> = self.query_dead_letters(sql).await?;
+
+        for letter in letters.iter() {
+            self.queue
+                .send(&letter.message.source_queue.to_string(), &letter.message.transaction_id)
+                .await?;
+            self.queue.delete(&self.queue_name.to_string(), letter.msg_id).await?;
+        }
+
+        Ok(letters.len())
+    }
+
+    async fn is_dead_lettered(&self, ids: &[ID]) -> Result<Vec<ID>> {
+        self.select_present_ids("message->'transaction_id'", ids).await
+    }
+}
+
+impl ProdQueue {
+    /// Returns those of `ids` that match `message_expr` of a message in this queue.
+    async fn select_present_ids<ID: Serialize + DeserializeOwned>(
+        &self,
+        message_expr: &str,
+        ids: &[ID],
+    ) -> Result<Vec<ID>> {
         if ids.is_empty() {
             return Ok(vec![]);
         }
 
-        let ids_str = ids.iter().map(|id| format!("'{}'::jsonb", id.to_string())).collect::<Vec<String>>().join(",");
-        let sql = format!("select message from pgmq.q_recalculation_queue where message in ({});", ids_str);
-        let result: Vec<ID> = self
+        let statement = present_ids_statement(&self.queue_name.to_string(), message_expr, ids)?;
+        let rows = self.db.query_all(statement).await?;
+
+        rows.iter()
+            .map(|row| {
+                let id = row.try_get::<serde_json::Value>("", "id")?;
+                serde_json::from_value::<ID>(id).map_err(|e| Error::Deserialization(Box::new(e)))
+            })
+            .collect()
+    }
+
+    async fn query_dead_letters<ID: DeserializeOwned>(
+        &self,
+        sql: String,
+    ) -> Result<Vec<QueueMessage<DeadLetter<ID>>>> {
+        let rows = self
             .db
             .query_all(Statement::from_string(DbBackend::Postgres, sql))
-            .await?
-            .iter()
-            .map(|row| 
-                row
-                    .try_get::<String>("", "message")
-                    .expect("Failed to get message")
-                    .parse::<ID>()
-                    .map_err(|_| "Failed to parse ID")
-                    .expect("Failed to parse ID"))
-            .collect::<Vec<ID>>();
-
-        Ok(result)
+            .await?;
+
+        let mut letters = Vec::with_capacity(rows.len());
+        for row in rows {
+            letters.push(QueueMessage {
+                msg_id: row.try_get::<i64>("", "msg_id")?,
+                read_count: row.try_get::<i32>("", "read_ct")?,
+                message: serde_json::from_value(row.try_get::<serde_json::Value>("", "message")?)
+                    .map_err(|e| Error::Deserialization(Box::new(e)))?,
+            });
+        }
+        Ok(letters)
+    }
+}
+
+/// Selects the `message_expr` of the messages of a queue matching one of `ids`, bound as `jsonb`
+/// parameters.
+fn present_ids_statement<ID: Serialize>(queue_name: &str, message_expr: &str, ids: &[ID]) -> Result<Statement> {
+    let placeholders = (1..=ids.len()).map(|i| format!("${}::jsonb", i)).collect::<Vec<String>>().join(",");
+    let values = ids
+        .iter()
+        .map(|id| Ok(sea_orm::Value::from(serde_json::to_value(id)?)))
+        .collect::<Result<Vec<sea_orm::Value>>>()?;
+    let sql = format!(
+        "select {expr} as id from pgmq.q_{} where {expr} in ({});",
+        queue_name,
+        placeholders,
+        expr = message_expr
+    );
+    Ok(Statement::from_sql_and_values(DbBackend::Postgres, sql, values))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_backoff_doubles_and_caps() {
+        assert_eq!(retry_backoff_seconds(1, 5, 600), 5);
+        assert_eq!(retry_backoff_seconds(2, 5, 600), 10);
+        assert_eq!(retry_backoff_seconds(4, 5, 600), 40);
+        assert_eq!(retry_backoff_seconds(20, 5, 600), 600);
+        assert_eq!(retry_backoff_seconds(0, 5, 600), 5);
+    }
+
+    #[test]
+    fn present_ids_are_bound_as_parameters() {
+        let ids = vec!["a'); drop table x; --".to_string(), "b".to_string()];
+        let statement = present_ids_statement("processing", "message", &ids).unwrap();
+
+        assert_eq!(
+            statement.sql,
+            "select message as id from pgmq.q_processing where message in ($1::jsonb,$2::jsonb);"
+        );
+        let values = statement.values.unwrap().0;
+        assert_eq!(values[0], sea_orm::Value::from(serde_json::json!("a'); drop table x; --")));
+        assert_eq!(values.len(), 2);
     }
 }
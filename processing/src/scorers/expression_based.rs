@@ -1,6 +1,5 @@
-use std::error::Error;
-
-use crate::{    
+use crate::{
+    error::Result,
     model::{ExpressionRule, Feature, ScoringResult, mongo_model::ScoringChannel}, scorers::Scorer
 };
 use async_trait::async_trait;
@@ -45,25 +44,16 @@ impl ExpressionBasedScorer {
 
         context
     }
-}
 
-#[async_trait]
-impl Scorer for ExpressionBasedScorer {
-    fn channel(&self) -> ScoringChannel {
-        self.activation.clone()
-    }
-
-    async fn score(
-        &self,
-        simple_features: &[Feature],
-        graph_features: &[Feature],
-    ) -> Result<Box<dyn ScoringResult>, Box<dyn Error + Send + Sync>> {
+    /// The rules of the channel that evaluate to true.
+    pub fn evaluate(&self, simple_features: &[Feature], graph_features: &[Feature]) -> Vec<ExpressionRule> {
         let context = self.setup_context(&simple_features, &graph_features);
 
-        let triggered_rules = self
+        self
             .activation
             .model
-            .expression_rules
+            .payload
+            .expression_rules()
             .iter()
             .filter_map(|expression| {
                 // Evaluate the expression
@@ -88,8 +78,21 @@ impl Scorer for ExpressionBasedScorer {
                         None
                     }
                 }
-            }).collect::<Vec<ExpressionRule>>();
-        
-        Ok(Box::new(triggered_rules))
+            }).collect::<Vec<ExpressionRule>>()
+    }
+}
+
+#[async_trait]
+impl Scorer for ExpressionBasedScorer {
+    fn channel(&self) -> ScoringChannel {
+        self.activation.clone()
+    }
+
+    async fn score(
+        &self,
+        simple_features: &[Feature],
+        graph_features: &[Feature],
+    ) -> Result<Box<dyn ScoringResult>> {
+        Ok(Box::new(self.evaluate(simple_features, graph_features)))
     }
 }
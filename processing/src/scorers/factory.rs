@@ -0,0 +1,133 @@
+use std::{collections::HashMap, sync::Arc};
+
+use crate::{
+    error::{Error, Result},
+    model::mongo_model::{ModelPayload, ScoringChannel},
+    scorers::{ExpressionBasedScorer, HybridScorer, MachineLearningScorer, Scorer},
+};
+
+pub type ScorerBuilder = Box<dyn Fn(ScoringChannel) -> Result<Arc<dyn Scorer>> + Send + Sync>;
+
+/// Builds scorers for channels by the kind of their model payload. The default factory knows
+/// the built-in kinds, `Custom` payloads need their scorer registered first.
+pub struct ScorerFactory {
+    builders: HashMap<String, ScorerBuilder>,
+}
+
+impl Default for ScorerFactory {
+    fn default() -> Self {
+        Self::empty()
+            .register(ModelPayload::EXPRESSION_BASED, |channel| {
+                Ok(Arc::new(ExpressionBasedScorer::new(channel)))
+            })
+            .register(ModelPayload::MACHINE_LEARNING, |channel| {
+                Ok(Arc::new(MachineLearningScorer::load(channel)?))
+            })
+            .register(ModelPayload::HYBRID, |channel| Ok(Arc::new(HybridScorer::load(channel)?)))
+    }
+}
+
+impl ScorerFactory {
+    pub fn empty() -> Self {
+        Self { builders: HashMap::new() }
+    }
+
+    /// Replaces the builder of `kind` if one is registered already.
+    pub fn register(
+        mut self,
+        kind: impl Into<String>,
+        builder: impl Fn(ScoringChannel) -> Result<Arc<dyn Scorer>> + Send + Sync + 'static,
+    ) -> Self {
+        self.builders.insert(kind.into(), Box::new(builder));
+        self
+    }
+
+    pub fn build(&self, channel: &ScoringChannel) -> Result<Arc<dyn Scorer>> {
+        let kind = channel.model.payload.kind();
+        let builder = self.builders.get(kind).ok_or_else(|| {
+            Error::Scoring(format!("no scorer registered for {} of channel {}", kind, channel.channel_name))
+        })?;
+        builder(channel.clone())
+    }
+
+    pub fn build_all(&self, channels: &[ScoringChannel]) -> Result<Vec<Arc<dyn Scorer>>> {
+        channels.iter().map(|channel| self.build(channel)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use mongodb::bson::{doc, oid::ObjectId};
+
+    use super::*;
+    use crate::model::{ExpressionRule, Feature, ScoringResult, mongo_model::ScoringModel};
+
+    fn channel(payload: ModelPayload) -> ScoringChannel {
+        ScoringChannel {
+            _id: ObjectId::new(),
+            channel_name: "test".to_string(),
+            model: ScoringModel {
+                name: "test".to_string(),
+                features_schema_version_major: 1,
+                features_schema_version_minor: 0,
+                version: "1".to_string(),
+                payload,
+                created_at: Utc::now().naive_utc(),
+            },
+            thresholds: None,
+            version: 1,
+            shadow: false,
+            is_active: true,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+
+    struct ConstantScorer(ScoringChannel);
+
+    #[async_trait]
+    impl Scorer for ConstantScorer {
+        fn channel(&self) -> ScoringChannel {
+            self.0.clone()
+        }
+
+        async fn score(&self, _: &[Feature], _: &[Feature]) -> Result<Box<dyn ScoringResult>> {
+            Ok(Box::new(Vec::<ExpressionRule>::new()))
+        }
+    }
+
+    #[test]
+    fn builds_built_in_and_registered_scorers_by_payload_kind() {
+        let factory = ScorerFactory::default()
+            .register("constant", |channel| Ok(Arc::new(ConstantScorer(channel))));
+        let rules = channel(ModelPayload::ExpressionBased { expression_rules: vec![] });
+        let custom = channel(ModelPayload::Custom { scorer: "constant".to_string(), config: doc! {} });
+
+        let scorers = factory.build_all(&[rules.clone(), custom.clone()]).unwrap();
+
+        assert_eq!(scorers[0].channel(), rules);
+        assert_eq!(scorers[1].channel(), custom);
+    }
+
+    #[test]
+    fn unregistered_kinds_and_missing_artifacts_fail() {
+        let factory = ScorerFactory::default();
+
+        let unknown = channel(ModelPayload::Custom { scorer: "neural".to_string(), config: doc! {} });
+        assert!(factory.build(&unknown).is_err_and(|e| e.to_string().contains("no scorer registered for neural")));
+
+        let missing = channel(ModelPayload::MachineLearning { artifact_path: "/nonexistent/model.json".to_string() });
+        assert!(factory.build(&missing).is_err_and(|e| e.to_string().contains("failed to read model artifact")));
+    }
+
+    #[test]
+    fn payloads_are_tagged_by_kind() {
+        let payload = ModelPayload::Hybrid { expression_rules: vec![], artifact_path: "model.json".to_string() };
+
+        let document = mongodb::bson::to_document(&payload).unwrap();
+
+        assert_eq!(document, doc! { "kind": "hybrid", "expression_rules": [], "artifact_path": "model.json" });
+        assert_eq!(mongodb::bson::from_document::<ModelPayload>(document).unwrap(), payload);
+    }
+}
@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+use crate::error::{Error, Result};
+
+/// A gradient-boosted tree ensemble for binary classification, loaded from JSON:
+///
+/// ```json
+/// { "feature_names": ["amount", "fraud_direct_count"], "base_margin": 0.0, "trees": [...] }
+/// ```
+///
+/// `trees` are XGBoost dumps (`Booster.get_dump(dump_format="json", with_stats=True)`), splits
+/// name either a declared feature or its position as `f<index>`. `base_margin` is the logit of
+/// XGBoost's `base_score`. Covers are optional, without them both children of a split weigh the
+/// same when computing contributions.
+#[derive(Debug, Clone)]
+pub struct GradientBoostedTrees {
+    feature_names: Vec<String>,
+    base_margin: f64,
+    trees: Vec<Tree>,
+}
+
+/// The outcome of a prediction, `bias + contributions.sum()` is the margin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Prediction {
+    pub probability: f64,
+    pub bias: f64,
+    /// Per declared feature, in the order of `feature_names`.
+    pub contributions: Vec<f64>,
+}
+
+#[derive(Debug, Clone)]
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Split {
+        feature: usize,
+        threshold: f64,
+        yes: usize,
+        no: usize,
+        missing: usize,
+        /// The expected leaf value below this node.
+        value: f64,
+    },
+    Leaf {
+        value: f64,
+    },
+}
+
+impl Node {
+    fn value(&self) -> f64 {
+        match self {
+            Node::Split { value, .. } | Node::Leaf { value } => *value,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ModelDump {
+    feature_names: Vec<String>,
+    #[serde(default)]
+    base_margin: f64,
+    trees: Vec<NodeDump>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum NodeDump {
+    Split {
+        nodeid: usize,
+        split: String,
+        split_condition: f64,
+        yes: usize,
+        no: usize,
+        missing: Option<usize>,
+        cover: Option<f64>,
+        children: Vec<NodeDump>,
+    },
+    Leaf {
+        nodeid: usize,
+        leaf: f64,
+        cover: Option<f64>,
+    },
+}
+
+impl NodeDump {
+    fn nodeid(&self) -> usize {
+        match self {
+            NodeDump::Split { nodeid, .. } | NodeDump::Leaf { nodeid, .. } => *nodeid,
+        }
+    }
+
+    fn cover(&self) -> Option<f64> {
+        match self {
+            NodeDump::Split { cover, .. } | NodeDump::Leaf { cover, .. } => *cover,
+        }
+    }
+}
+
+impl GradientBoostedTrees {
+    pub fn from_json(json: &str) -> Result<Self> {
+        let dump: ModelDump = serde_json::from_str(json)
+            .map_err(|e| Error::Scoring(format!("invalid gradient boosted trees model: {}", e)))?;
+        let indexes: HashMap<&str, usize> =
+            dump.feature_names.iter().enumerate().map(|(i, name)| (name.as_str(), i)).collect();
+        let resolve = |split: &str| -> Result<usize> {
+            indexes
+                .get(split)
+                .copied()
+                .or_else(|| split.strip_prefix('f').and_then(|i| i.parse().ok()))
+                .filter(|i| *i < dump.feature_names.len())
+                .ok_or_else(|| Error::Scoring(format!("split on undeclared feature: {}", split)))
+        };
+
+        let mut trees = Vec::with_capacity(dump.trees.len());
+        for root in &dump.trees {
+            let mut nodes = HashMap::new();
+            flatten(root, &resolve, &mut nodes)?;
+            let mut ordered = Vec::with_capacity(nodes.len());
+            for id in 0..nodes.len() {
+                ordered.push(
+                    nodes
+                        .remove(&id)
+                        .ok_or_else(|| Error::Scoring(format!("tree node ids are not contiguous, missing {}", id)))?,
+                );
+            }
+            trees.push(Tree { nodes: ordered });
+        }
+
+        Ok(Self { feature_names: dump.feature_names, base_margin: dump.base_margin, trees })
+    }
+
+    pub fn feature_names(&self) -> &[String] {
+        &self.feature_names
+    }
+
+    /// `inputs` follow `feature_names`, `NaN` takes the missing branch.
+    pub fn predict(&self, inputs: &[f64]) -> Prediction {
+        let mut bias = self.base_margin;
+        let mut contributions = vec![0.0; self.feature_names.len()];
+        for tree in &self.trees {
+            let mut node = &tree.nodes[0];
+            bias += node.value();
+            // A valid tree reaches a leaf in fewer steps than it has nodes
+            let mut steps = 0;
+            while let Node::Split { feature, threshold, yes, no, missing, value } = node {
+                steps += 1;
+                if steps > tree.nodes.len() {
+                    break;
+                }
+                let input = inputs.get(*feature).copied().unwrap_or(f64::NAN);
+                let next = if input.is_nan() {
+                    *missing
+                } else if input < *threshold {
+                    *yes
+                } else {
+                    *no
+                };
+                node = &tree.nodes[next];
+                contributions[*feature] += node.value() - value;
+            }
+        }
+
+        let margin = bias + contributions.iter().sum::<f64>();
+        Prediction { probability: 1.0 / (1.0 + (-margin).exp()), bias, contributions }
+    }
+}
+
+/// Adds the node and everything below it, returns the expected leaf value and cover.
+fn flatten(
+    dump: &NodeDump,
+    resolve: &impl Fn(&str) -> Result<usize>,
+    nodes: &mut HashMap<usize, Node>,
+) -> Result<(f64, Option<f64>)> {
+    let node = match dump {
+        NodeDump::Leaf { leaf, .. } => Node::Leaf { value: *leaf },
+        NodeDump::Split { split, split_condition, yes, no, missing, children, .. } => {
+            let mut expected = HashMap::new();
+            for child in children {
+                expected.insert(child.nodeid(), flatten(child, resolve, nodes)?);
+            }
+            let child = |id: &usize| {
+                expected
+                    .get(id)
+                    .copied()
+                    .ok_or_else(|| Error::Scoring(format!("split {} has no child {}", dump.nodeid(), id)))
+            };
+            if *yes == dump.nodeid() || *no == dump.nodeid() {
+                return Err(Error::Scoring(format!("split {} is its own child", dump.nodeid())));
+            }
+            let ((yes_value, yes_cover), (no_value, no_cover)) = (child(yes)?, child(no)?);
+            let missing = missing.unwrap_or(*yes);
+            if missing != *yes && missing != *no {
+                return Err(Error::Scoring(format!(
+                    "split {} sends missing values to {}, neither of its children",
+                    dump.nodeid(),
+                    missing
+                )));
+            }
+            let value = match (yes_cover, no_cover) {
+                (Some(y), Some(n)) if y + n > 0.0 => (yes_value * y + no_value * n) / (y + n),
+                _ => (yes_value + no_value) / 2.0,
+            };
+            Node::Split {
+                feature: resolve(split)?,
+                threshold: *split_condition,
+                yes: *yes,
+                no: *no,
+                missing,
+                value,
+            }
+        }
+    };
+    let value = node.value();
+    if nodes.insert(dump.nodeid(), node).is_some() {
+        return Err(Error::Scoring(format!("duplicate tree node id {}", dump.nodeid())));
+    }
+    Ok((value, dump.cover()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn model() -> GradientBoostedTrees {
+        GradientBoostedTrees::from_json(
+            r#"{
+                "feature_names": ["amount", "fraud_direct_count"],
+                "base_margin": -1.0,
+                "trees": [
+                    { "nodeid": 0, "split": "amount", "split_condition": 100.0, "yes": 1, "no": 2, "missing": 2, "cover": 4.0,
+                      "children": [
+                        { "nodeid": 1, "leaf": -0.5, "cover": 3.0 },
+                        { "nodeid": 2, "leaf": 1.5, "cover": 1.0 }
+                      ] },
+                    { "nodeid": 0, "split": "f1", "split_condition": 1.0, "yes": 1, "no": 2,
+                      "children": [
+                        { "nodeid": 1, "leaf": 0.0 },
+                        { "nodeid": 2, "leaf": 2.0 }
+                      ] }
+                ]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn contributions_add_up_to_the_margin() {
+        let prediction = model().predict(&[500.0, 2.0]);
+
+        // Base -1.0 plus leaves 1.5 and 2.0, the roots expect 0.0 and 1.0
+        assert_eq!(prediction.bias, 0.0);
+        assert_eq!(prediction.contributions, vec![1.5, 1.0]);
+        assert!((prediction.probability - 1.0 / (1.0 + (-2.5f64).exp())).abs() < 1e-12);
+    }
+
+    #[test]
+    fn missing_inputs_take_the_missing_branch() {
+        let prediction = model().predict(&[f64::NAN, f64::NAN]);
+
+        assert_eq!(prediction.contributions, vec![1.5, -1.0]);
+    }
+
+    #[test]
+    fn splits_on_undeclared_features_are_rejected() {
+        let error = GradientBoostedTrees::from_json(
+            r#"{ "feature_names": ["amount"], "trees": [
+                { "nodeid": 0, "split": "f3", "split_condition": 1.0, "yes": 1, "no": 2,
+                  "children": [{ "nodeid": 1, "leaf": 0.0 }, { "nodeid": 2, "leaf": 1.0 }] }
+            ] }"#,
+        )
+        .unwrap_err();
+
+        assert!(error.to_string().contains("undeclared feature: f3"));
+    }
+
+    #[test]
+    fn missing_branches_outside_the_children_are_rejected() {
+        let error = GradientBoostedTrees::from_json(
+            r#"{ "feature_names": ["amount"], "trees": [
+                { "nodeid": 0, "split": "amount", "split_condition": 1.0, "yes": 1, "no": 2, "missing": 0,
+                  "children": [{ "nodeid": 1, "leaf": 0.0 }, { "nodeid": 2, "leaf": 1.0 }] }
+            ] }"#,
+        )
+        .unwrap_err();
+
+        assert!(error.to_string().contains("split 0 sends missing values to 0"));
+    }
+
+    #[test]
+    fn duplicate_and_self_referencing_node_ids_are_rejected() {
+        let duplicate = GradientBoostedTrees::from_json(
+            r#"{ "feature_names": ["amount"], "trees": [
+                { "nodeid": 0, "split": "amount", "split_condition": 1.0, "yes": 1, "no": 2,
+                  "children": [{ "nodeid": 1, "leaf": 0.0 }, { "nodeid": 1, "leaf": 1.0 }] }
+            ] }"#,
+        )
+        .unwrap_err();
+        assert!(duplicate.to_string().contains("duplicate tree node id 1"));
+
+        let own_child = GradientBoostedTrees::from_json(
+            r#"{ "feature_names": ["amount"], "trees": [
+                { "nodeid": 0, "split": "amount", "split_condition": 1.0, "yes": 0, "no": 2,
+                  "children": [{ "nodeid": 0, "leaf": 0.0 }, { "nodeid": 2, "leaf": 1.0 }] }
+            ] }"#,
+        )
+        .unwrap_err();
+        assert!(own_child.to_string().contains("split 0 is its own child"));
+    }
+
+    #[test]
+    fn prediction_stops_on_cyclic_trees() {
+        let split = Node::Split { feature: 0, threshold: 1.0, yes: 0, no: 0, missing: 0, value: 0.0 };
+        let model = GradientBoostedTrees {
+            feature_names: vec!["amount".to_string()],
+            base_margin: 0.0,
+            trees: vec![Tree { nodes: vec![split] }],
+        };
+
+        assert_eq!(model.predict(&[0.0]).bias, 0.0);
+    }
+}
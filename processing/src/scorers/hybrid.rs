@@ -0,0 +1,68 @@
+use async_trait::async_trait;
+
+use crate::{
+    error::Result,
+    model::{ExpressionRule, Feature, ScoringResult, mongo_model::{RuleScore, ScoringChannel}},
+    scorers::{ExpressionBasedScorer, MachineLearningResult, MachineLearningScorer, Scorer},
+};
+
+/// Runs the expression rules and the model of a hybrid payload side by side.
+pub struct HybridScorer {
+    rules: ExpressionBasedScorer,
+    model: MachineLearningScorer,
+}
+
+impl HybridScorer {
+    pub fn load(activation: ScoringChannel) -> Result<Self> {
+        Ok(Self {
+            model: MachineLearningScorer::load(activation.clone())?,
+            rules: ExpressionBasedScorer::new(activation),
+        })
+    }
+}
+
+/// Triggered rules on top of a model prediction.
+pub struct HybridResult {
+    pub triggered_rules: Vec<ExpressionRule>,
+    pub prediction: MachineLearningResult,
+}
+
+impl ScoringResult for HybridResult {
+    /// Rule scores plus the probability in percent.
+    fn get_total_score(&self) -> i32 {
+        self.triggered_rules.get_total_score() + self.prediction.get_total_score()
+    }
+    fn get_triggered_rules(&self) -> Vec<String> {
+        self.triggered_rules.get_triggered_rules()
+    }
+    /// Rules first, then feature contributions.
+    fn get_rule_scores(&self) -> Vec<RuleScore> {
+        let mut scores = self.triggered_rules.get_rule_scores();
+        scores.extend(self.prediction.get_rule_scores());
+        scores
+    }
+    fn get_result_payload(&self) -> serde_json::Value {
+        serde_json::json!({
+            "triggered_rules": self.triggered_rules.get_result_payload(),
+            "prediction": self.prediction.get_result_payload(),
+        })
+    }
+}
+
+#[async_trait]
+impl Scorer for HybridScorer {
+    fn channel(&self) -> ScoringChannel {
+        self.rules.channel()
+    }
+
+    async fn score(
+        &self,
+        simple_features: &[Feature],
+        graph_features: &[Feature],
+    ) -> Result<Box<dyn ScoringResult>> {
+        Ok(Box::new(HybridResult {
+            triggered_rules: self.rules.evaluate(simple_features, graph_features),
+            prediction: self.model.predict(simple_features, graph_features),
+        }))
+    }
+}
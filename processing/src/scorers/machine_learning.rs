@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use serde::Serialize;
+
+use crate::{
+    error::{Error, Result},
+    model::{Feature, FeatureValue, ScoringResult, mongo_model::{RuleScore, ScoringChannel}},
+    scorers::{GradientBoostedTrees, Scorer},
+};
+
+/// Number of feature contributions kept in a result.
+pub const TOP_CONTRIBUTIONS: usize = 5;
+
+/// Scores with a gradient-boosted tree ensemble on the CPU. The model artifact is read once,
+/// from the artifact path of the model payload, when the scorer is created.
+pub struct MachineLearningScorer {
+    activation: ScoringChannel,
+    model: GradientBoostedTrees,
+}
+
+impl MachineLearningScorer {
+    pub fn new(activation: ScoringChannel, model: GradientBoostedTrees) -> Self {
+        Self { activation, model }
+    }
+
+    pub fn load(activation: ScoringChannel) -> Result<Self> {
+        let path = activation.model.payload.artifact_path().ok_or_else(|| {
+            Error::Scoring(format!("model {} has no artifact_path", activation.model.name))
+        })?;
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| Error::Scoring(format!("failed to read model artifact {}: {}", path, e)))?;
+        let model = GradientBoostedTrees::from_json(&json)?;
+        Ok(Self::new(activation, model))
+    }
+
+    /// Inputs in the order of the declared feature names. Features that are absent or not
+    /// numeric are missing values.
+    fn inputs(&self, simple_features: &[Feature], graph_features: &[Feature]) -> Vec<f64> {
+        let by_name: HashMap<&str, &FeatureValue> = simple_features
+            .iter()
+            .chain(graph_features)
+            .map(|feature| (feature.name.as_str(), feature.value.as_ref()))
+            .collect();
+        self.model
+            .feature_names()
+            .iter()
+            .map(|name| match by_name.get(name.as_str()) {
+                Some(FeatureValue::Int(value)) => *value as f64,
+                Some(FeatureValue::Double(value)) => *value,
+                Some(FeatureValue::Bool(value)) => f64::from(u8::from(*value)),
+                _ => f64::NAN,
+            })
+            .collect()
+    }
+
+    pub fn predict(&self, simple_features: &[Feature], graph_features: &[Feature]) -> MachineLearningResult {
+        let prediction = self.model.predict(&self.inputs(simple_features, graph_features));
+
+        let mut contributions: Vec<FeatureContribution> = self
+            .model
+            .feature_names()
+            .iter()
+            .zip(prediction.contributions)
+            .filter(|(_, contribution)| *contribution != 0.0)
+            .map(|(name, contribution)| FeatureContribution { name: name.clone(), contribution })
+            .collect();
+        contributions.sort_by(|a, b| b.contribution.abs().total_cmp(&a.contribution.abs()));
+        contributions.truncate(TOP_CONTRIBUTIONS);
+
+        MachineLearningResult { probability: prediction.probability, contributions }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeatureContribution {
+    pub name: String,
+    /// In log-odds.
+    pub contribution: f64,
+}
+
+/// A fraud probability with the features that moved it the most.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MachineLearningResult {
+    pub probability: f64,
+    /// Largest absolute contributions first, at most `TOP_CONTRIBUTIONS`.
+    pub contributions: Vec<FeatureContribution>,
+}
+
+impl ScoringResult for MachineLearningResult {
+    /// The probability in percent, comparable with decision thresholds.
+    fn get_total_score(&self) -> i32 {
+        (self.probability * 100.0).round() as i32
+    }
+    fn get_triggered_rules(&self) -> Vec<String> {
+        Vec::new()
+    }
+    /// Contributions in hundredths of log-odds.
+    fn get_rule_scores(&self) -> Vec<RuleScore> {
+        self.contributions
+            .iter()
+            .map(|c| RuleScore { name: c.name.clone(), score: (c.contribution * 100.0).round() as i32 })
+            .collect()
+    }
+    fn get_result_payload(&self) -> serde_json::Value {
+        serde_json::json!(self)
+    }
+}
+
+#[async_trait]
+impl Scorer for MachineLearningScorer {
+    fn channel(&self) -> ScoringChannel {
+        self.activation.clone()
+    }
+
+    async fn score(
+        &self,
+        simple_features: &[Feature],
+        graph_features: &[Feature],
+    ) -> Result<Box<dyn ScoringResult>> {
+        Ok(Box::new(self.predict(simple_features, graph_features)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mongodb::bson::oid::ObjectId;
+
+    use super::*;
+    use crate::model::mongo_model::{ModelPayload, ScoringModel};
+
+    fn scorer() -> MachineLearningScorer {
+        let model = GradientBoostedTrees::from_json(
+            r#"{
+                "feature_names": ["amount", "is_new_customer", "fraud_direct_count"],
+                "trees": [
+                    { "nodeid": 0, "split": "amount", "split_condition": 100.0, "yes": 1, "no": 2,
+                      "children": [{ "nodeid": 1, "leaf": -1.0 }, { "nodeid": 2, "leaf": 1.0 }] },
+                    { "nodeid": 0, "split": "is_new_customer", "split_condition": 0.5, "yes": 1, "no": 2,
+                      "children": [{ "nodeid": 1, "leaf": -0.2 }, { "nodeid": 2, "leaf": 0.2 }] }
+                ]
+            }"#,
+        )
+        .unwrap();
+        let channel = ScoringChannel {
+            _id: ObjectId::new(),
+            channel_name: "ml".to_string(),
+            model: ScoringModel {
+                name: "gbt".to_string(),
+                features_schema_version_major: 1,
+                features_schema_version_minor: 0,
+                version: "1".to_string(),
+                payload: ModelPayload::MachineLearning { artifact_path: "gbt.json".to_string() },
+                created_at: Utc::now().naive_utc(),
+            },
+            thresholds: None,
+            version: 1,
+            shadow: false,
+            is_active: true,
+            created_at: Utc::now().naive_utc(),
+        };
+        MachineLearningScorer::new(channel, model)
+    }
+
+    fn feature(name: &str, value: FeatureValue) -> Feature {
+        Feature { name: name.to_string(), value: Box::new(value) }
+    }
+
+    #[tokio::test]
+    async fn maps_features_by_name_and_ranks_contributions() {
+        let simple = vec![feature("is_new_customer", FeatureValue::Bool(true)), feature("amount", FeatureValue::Int(250))];
+        let graph = vec![feature("fraud_direct_count", FeatureValue::Int(3))];
+
+        let result = scorer().score(&simple, &graph).await.unwrap();
+
+        assert_eq!(result.get_total_score(), 77);
+        assert_eq!(
+            result.get_rule_scores(),
+            vec![
+                RuleScore { name: "amount".to_string(), score: 100 },
+                RuleScore { name: "is_new_customer".to_string(), score: 20 },
+            ]
+        );
+    }
+}
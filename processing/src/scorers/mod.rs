@@ -1,9 +1,18 @@
 pub mod expression_based;
-
-use std::error::Error;
+pub mod factory;
+pub mod gbt;
+pub mod hybrid;
+pub mod machine_learning;
+pub mod scorer_set;
 
 pub use expression_based::*;
+pub use factory::*;
+pub use gbt::*;
+pub use hybrid::*;
+pub use machine_learning::*;
+pub use scorer_set::*;
 
+use crate::error::Result;
 use crate::model::{Feature, ScoringResult, mongo_model::ScoringChannel};
 use async_trait::async_trait;
 
@@ -14,5 +23,5 @@ pub trait Scorer: Send + Sync + 'static {
         &self,
         simple_features: &[Feature],
         graph_features: &[Feature]
-    ) -> Result<Box<dyn ScoringResult>, Box<dyn Error + Send + Sync>>;
+    ) -> Result<Box<dyn ScoringResult>>;
 }
@@ -0,0 +1,189 @@
+use std::sync::{Arc, RwLock};
+
+use crate::{
+    error::{Error, Result},
+    model::mongo_model::ScoringChannel,
+    scorers::{Scorer, ScorerFactory},
+};
+
+/// The scorers of the active channels. Reloads swap the whole set, so a transaction is always
+/// scored by the channels of a single snapshot.
+pub struct ScorerSet {
+    factory: Arc<ScorerFactory>,
+    scorers: RwLock<Arc<Vec<Arc<dyn Scorer>>>>,
+}
+
+/// The outcome of a reload: whether the set was replaced and the channels that could not be
+/// applied. Each failed channel keeps its previous scorer, new ones are left out.
+#[derive(Debug, Default)]
+pub struct Reload {
+    pub replaced: bool,
+    pub failures: Vec<Error>,
+}
+
+impl ScorerSet {
+    pub fn new(factory: ScorerFactory, scorers: Vec<Arc<dyn Scorer>>) -> Self {
+        Self { factory: Arc::new(factory), scorers: RwLock::new(Arc::new(scorers)) }
+    }
+
+    /// Builds the scorers of `channels`, failing on the first channel that does not build.
+    pub async fn load(factory: ScorerFactory, channels: &[ScoringChannel]) -> Result<Self> {
+        let set = Self::new(factory, vec![]);
+        match set.reload(channels).await.failures.into_iter().next() {
+            Some(error) => Err(error),
+            None => Ok(set),
+        }
+    }
+
+    pub fn current(&self) -> Arc<Vec<Arc<dyn Scorer>>> {
+        self.scorers.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Applies `active` channel by channel: deactivated channels are dropped, new and edited ones
+    /// are built and unchanged ones keep their scorer. A channel that fails to build or was
+    /// edited without a new `version` keeps its previous scorer, or is left out if it is new,
+    /// without holding back the other channels.
+    pub async fn reload(&self, active: &[ScoringChannel]) -> Reload {
+        let in_use = self.current();
+        let in_use_channels: Vec<ScoringChannel> = in_use.iter().map(|scorer| scorer.channel()).collect();
+        if !channels_changed(&in_use_channels, active) {
+            return Reload::default();
+        }
+
+        let mut failures = Vec::new();
+        let mut scorers = Vec::with_capacity(active.len());
+        for channel in active {
+            let previous = in_use.iter().find(|scorer| scorer.channel()._id == channel._id);
+            let built = match previous {
+                Some(scorer) if scorer.channel() == *channel => Ok(scorer.clone()),
+                Some(scorer) if scorer.channel().version == channel.version => Err(Error::Scoring(format!(
+                    "channel {} ({}) was edited without incrementing its version {}",
+                    channel.channel_name, channel._id, channel.version
+                ))),
+                _ => self.build(channel).await,
+            };
+            match built {
+                Ok(scorer) => scorers.push(scorer),
+                Err(error) => {
+                    failures.push(error);
+                    scorers.extend(previous.cloned());
+                }
+            }
+        }
+
+        let channels: Vec<ScoringChannel> = scorers.iter().map(|scorer| scorer.channel()).collect();
+        let replaced = channels_changed(&in_use_channels, &channels);
+        if replaced {
+            *self.scorers.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(scorers);
+        }
+        Reload { replaced, failures }
+    }
+
+    /// Builds off the runtime, model artifacts are read and parsed synchronously.
+    async fn build(&self, channel: &ScoringChannel) -> Result<Arc<dyn Scorer>> {
+        let (factory, channel) = (self.factory.clone(), channel.clone());
+        let name = channel.channel_name.clone();
+        tokio::task::spawn_blocking(move || factory.build(&channel))
+            .await
+            .map_err(|e| Error::Scoring(format!("building the scorer of channel {} failed: {}", name, e)))?
+    }
+}
+
+/// Channels were activated, deactivated or edited, regardless of order.
+fn channels_changed(in_use: &[ScoringChannel], active: &[ScoringChannel]) -> bool {
+    in_use.len() != active.len()
+        || active
+            .iter()
+            .any(|channel| !in_use.iter().any(|used| used == channel))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mongodb::bson::oid::ObjectId;
+
+    use super::*;
+    use crate::model::mongo_model::{DecisionThresholds, ModelPayload, ScoringModel};
+
+    fn channel(version: i64) -> ScoringChannel {
+        ScoringChannel {
+            _id: ObjectId::new(),
+            channel_name: "rules".to_string(),
+            model: ScoringModel {
+                name: "rules".to_string(),
+                features_schema_version_major: 1,
+                features_schema_version_minor: 0,
+                version: "1".to_string(),
+                payload: ModelPayload::ExpressionBased { expression_rules: vec![] },
+                created_at: Utc::now().naive_utc(),
+            },
+            thresholds: None,
+            version,
+            shadow: false,
+            is_active: true,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[tokio::test]
+    async fn reloads_only_when_channels_change() {
+        let (first, second) = (channel(1), channel(1));
+        let set = ScorerSet::new(ScorerFactory::default(), vec![]);
+
+        assert!(set.reload(&[first.clone(), second.clone()]).await.replaced);
+        assert!(!set.reload(&[second.clone(), first.clone()]).await.replaced);
+
+        let edited = ScoringChannel { version: 2, ..second.clone() };
+        assert!(set.reload(&[first.clone(), edited.clone()]).await.replaced);
+        assert_eq!(set.current()[1].channel(), edited);
+
+        assert!(set.reload(&[first.clone()]).await.replaced);
+        assert_eq!(set.current().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn edits_without_a_new_version_keep_the_previous_scorer() {
+        let (rules, other) = (channel(1), channel(1));
+        let set = ScorerSet::load(ScorerFactory::default(), &[rules.clone(), other.clone()]).await.unwrap();
+
+        let edited = ScoringChannel { thresholds: Some(DecisionThresholds { review: 50, decline: 80 }), ..rules.clone() };
+        let other_v2 = ScoringChannel { version: 2, ..other.clone() };
+        let reload = set.reload(&[edited, other_v2.clone()]).await;
+
+        assert!(reload.replaced);
+        assert_eq!(reload.failures.len(), 1);
+        assert!(reload.failures[0].to_string().contains("without incrementing its version 1"));
+        assert_eq!(set.current()[0].channel(), rules);
+        assert_eq!(set.current()[1].channel(), other_v2);
+    }
+
+    #[tokio::test]
+    async fn failed_builds_only_hold_back_their_channel() {
+        let (rules, dropped, edited) = (channel(1), channel(1), channel(1));
+        let set = ScorerSet::load(ScorerFactory::default(), &[rules.clone(), dropped, edited.clone()]).await.unwrap();
+
+        let broken_payload = ModelPayload::MachineLearning { artifact_path: "/nonexistent/model.json".to_string() };
+        let mut broken_edit = ScoringChannel { version: 2, ..edited.clone() };
+        broken_edit.model.payload = broken_payload.clone();
+        let mut broken_new = channel(1);
+        broken_new.model.payload = broken_payload;
+        let added = channel(1);
+
+        let reload = set.reload(&[rules.clone(), broken_edit, broken_new, added.clone()]).await;
+
+        assert!(reload.replaced);
+        assert_eq!(reload.failures.len(), 2);
+        let channels: Vec<ScoringChannel> = set.current().iter().map(|scorer| scorer.channel()).collect();
+        assert_eq!(channels, vec![rules, edited, added]);
+    }
+
+    #[tokio::test]
+    async fn loading_fails_when_a_channel_does_not_build() {
+        let mut broken = channel(1);
+        broken.model.payload = ModelPayload::MachineLearning { artifact_path: "/nonexistent/model.json".to_string() };
+
+        let error = ScorerSet::load(ScorerFactory::default(), &[channel(1), broken]).await.err().unwrap();
+
+        assert!(error.to_string().contains("failed to read model artifact"));
+    }
+}
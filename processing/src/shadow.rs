@@ -0,0 +1,114 @@
+use std::collections::{HashMap, HashSet};
+
+use mongodb::bson::oid::ObjectId;
+
+use crate::model::{ChannelComparison, RuleCount, mongo_model::ScoringEvent};
+
+/// Compares champion and challenger events of the same transactions, pairs as returned by
+/// `CommonStorage::get_shadow_event_pairs`.
+pub fn compare_channels(
+    champion_id: ObjectId,
+    challenger_id: ObjectId,
+    pairs: &[(ScoringEvent, ScoringEvent)],
+) -> ChannelComparison {
+    let decided: Vec<bool> = pairs
+        .iter()
+        .filter_map(|(champion, challenger)| Some(champion.decision? == challenger.decision?))
+        .collect();
+    let agreement_rate = (!decided.is_empty())
+        .then(|| decided.iter().filter(|agreed| **agreed).count() as f64 / decided.len() as f64);
+
+    let deltas: Vec<i32> = pairs
+        .iter()
+        .map(|(champion, challenger)| challenger.total_score - champion.total_score)
+        .collect();
+    let mean = |values: Vec<f64>| (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for (champion, challenger) in pairs {
+        let fired: HashSet<&str> = champion.triggered_rules.iter().map(String::as_str).collect();
+        for rule in challenger.triggered_rules.iter().filter(|rule| !fired.contains(rule.as_str())) {
+            *counts.entry(rule).or_default() += 1;
+        }
+    }
+    let mut challenger_only_rules: Vec<RuleCount> = counts
+        .into_iter()
+        .map(|(name, count)| RuleCount { name: name.to_string(), count })
+        .collect();
+    challenger_only_rules.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.name.cmp(&b.name)));
+
+    ChannelComparison {
+        champion_id,
+        challenger_id,
+        compared: pairs.len(),
+        agreement_rate,
+        mean_score_delta: mean(deltas.iter().map(|d| *d as f64).collect()),
+        mean_abs_score_delta: mean(deltas.iter().map(|d| d.abs() as f64).collect()),
+        max_abs_score_delta: deltas.iter().map(|d| d.abs()).max(),
+        challenger_only_rules,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+
+    use super::*;
+    use crate::model::Decision;
+
+    fn event(channel_id: ObjectId, transaction_id: ObjectId, total_score: i32, decision: Option<Decision>, rules: &[&str]) -> ScoringEvent {
+        ScoringEvent {
+            _id: ObjectId::new(),
+            transaction_id,
+            channel_id,
+            channel_version: 1,
+            triggered_rules: rules.iter().map(|rule| rule.to_string()).collect(),
+            total_score,
+            rule_scores: vec![],
+            decision,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn reports_agreement_score_deltas_and_challenger_only_rules() {
+        let (champion, challenger) = (ObjectId::new(), ObjectId::new());
+        let (a, b, c) = (ObjectId::new(), ObjectId::new(), ObjectId::new());
+        let pairs = vec![
+            (
+                event(champion, a, 40, Some(Decision::Approve), &["amount"]),
+                event(challenger, a, 60, Some(Decision::Review), &["amount", "velocity", "geo"]),
+            ),
+            (
+                event(champion, b, 90, Some(Decision::Decline), &["amount"]),
+                event(challenger, b, 80, Some(Decision::Decline), &["velocity"]),
+            ),
+            (event(champion, c, 10, None, &[]), event(challenger, c, 10, Some(Decision::Approve), &[])),
+        ];
+
+        let report = compare_channels(champion, challenger, &pairs);
+
+        assert_eq!(report.compared, 3);
+        assert_eq!(report.agreement_rate, Some(0.5));
+        assert_eq!(report.mean_score_delta, Some(10.0 / 3.0));
+        assert_eq!(report.mean_abs_score_delta, Some(10.0));
+        assert_eq!(report.max_abs_score_delta, Some(20));
+        assert_eq!(
+            report.challenger_only_rules,
+            vec![
+                RuleCount { name: "velocity".to_string(), count: 2 },
+                RuleCount { name: "geo".to_string(), count: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_comparisons_have_no_rates() {
+        let report = compare_channels(ObjectId::new(), ObjectId::new(), &[]);
+
+        assert_eq!(report.compared, 0);
+        assert_eq!(report.agreement_rate, None);
+        assert_eq!(report.mean_score_delta, None);
+        assert_eq!(report.max_abs_score_delta, None);
+    }
+}
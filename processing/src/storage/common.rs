@@ -1,9 +1,11 @@
+use crate::error::{Error, Result};
 use crate::model::{
     Feature, mongo_model::{ScoringChannel, ScoringEvent, Transaction}, processible::{ColumnValueTrait, Filter}, *
 };
 use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+use common::config::{GraphFilterConfig, TraversalConfig};
 use tracing::debug;
-use std::error::Error;
 use jsonschema::validate;
 use serde_json::{Value, json};
 
@@ -13,7 +15,7 @@ pub trait CommonStorage<ID: Send + Sync + PartialEq>: Send + Sync {
     fn validate_features(
         &self,
         features: &[Feature],
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    ) -> Result<()> {
         let features_json = serde_json::to_value(features)?;
         debug!(
             "Raw features JSON string: {}",
@@ -22,7 +24,7 @@ pub trait CommonStorage<ID: Send + Sync + PartialEq>: Send + Sync {
         let validation_result = validate(&self.get_features_schema(), &features_json);
         if let Err(errors) = validation_result {
             debug!("Validation error details: {:?}", errors);
-            return Err(format!("Feature validation failed: {:?}", errors).into());
+            return Err(Error::InvalidFeatures(format!("{:?}", errors)));
         }
         Ok(())
     }
@@ -32,54 +34,78 @@ pub trait CommonStorage<ID: Send + Sync + PartialEq>: Send + Sync {
         payload_number: String,
         payload: serde_json::Value,
         schema_version: (i32, i32),
-    ) -> Result<ID, Box<dyn Error + Send + Sync>>;
+    ) -> Result<ID>;
 
     async fn get_transaction(
         &self,
         transaction_id: ID,
-    ) -> Result<Transaction, Box<dyn Error + Send + Sync>>;
+    ) -> Result<Transaction>;
+
+    async fn get_latest_transaction_ids(
+        &self,
+        payload_numbers: &[String],
+    ) -> Result<Vec<ID>>;
+
+    async fn get_latest_neighbor_summaries(
+        &self,
+        payload_numbers: &[String],
+    ) -> Result<Vec<NeighborSummary<ID>>>;
+
+    /// Creation dates and the requested simple features of the latest versions of neighbors.
+    async fn get_neighbor_snapshots(
+        &self,
+        payload_numbers: &[String],
+        feature_names: &[String],
+    ) -> Result<Vec<NeighborSnapshot>>;
+
+    /// Latest transaction versions created before `created_before` that were never processed,
+    /// in id order and starting after `after`, so callers can page past ids they can't enqueue.
+    async fn find_unprocessed_transaction_ids(
+        &self,
+        created_before: chrono::NaiveDateTime,
+        after: Option<ID>,
+        limit: i64,
+    ) -> Result<Vec<ID>>;
 
     async fn filter_transactions(
         &self,
         filters: &[Filter<Box<dyn ColumnValueTrait>>],
-    ) -> Result<Vec<Transaction>, Box<dyn Error + Send + Sync>>;
+    ) -> Result<Vec<Transaction>>;
 
     async fn mark_transaction_processed(
         &self,
         transaction_id: ID,
-    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    ) -> Result<()>;
 
     async fn save_features<'a>(
         &self,
         transaction_id: ID,
         simple_features: &'a Option<&'a [Feature]>,
         graph_features: &'a [Feature],
-    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    ) -> Result<()>;
 
     async fn get_active_model_activations(
         &self,
-    ) -> Result<Vec<ScoringChannel>, Box<dyn Error + Send + Sync>>;
+    ) -> Result<Vec<ScoringChannel>>;
 
     async fn save_scores(
         &self,
         transaction_id: ID,
         channel: ScoringChannel,
         scoring_result: Box<dyn ScoringResult>,
-    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    ) -> Result<()>;
 
     async fn find_connected_transactions(
         &self,
         payload_number: &str,
-        max_depth: Option<i32>,
-        limit_count: Option<i32>,
-        filter_config: Option<serde_json::Value>,
-        min_confidence: Option<i32>,
-    ) -> Result<Vec<ConnectedTransaction>, Box<dyn Error + Send + Sync>>;
+        traversal: &TraversalConfig,
+        filter_config: Option<&GraphFilterConfig>,
+    ) -> Result<Vec<ConnectedTransaction>>;
 
     async fn get_direct_connections(
         &self,
         payload_number: &str,
-    ) -> Result<Vec<DirectConnection>, Box<dyn Error + Send + Sync>>;
+    ) -> Result<Vec<DirectConnection>>;
 
     async fn save_matching_fields_with_timespace(
         &self,
@@ -95,13 +121,13 @@ pub trait CommonStorage<ID: Send + Sync + PartialEq>: Send + Sync {
         lat_gamma: Option<f64>,
         long_delta: Option<f64>,
         lat_delta: Option<f64>,
-    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    ) -> Result<()>;
 
     async fn save_matching_fields(
         &self,
         transaction_id: &ID,
         matching_fields: &[MatchingField],
-    ) -> Result<(), Box<dyn Error + Send + Sync>> {
+    ) -> Result<()> {
         self.save_matching_fields_with_timespace(
             transaction_id,
             matching_fields,
@@ -119,10 +145,53 @@ pub trait CommonStorage<ID: Send + Sync + PartialEq>: Send + Sync {
         .await
     }
 
+    /// Unlinks the payload from the match nodes its previous version matched and this one no
+    /// longer does. Returns the neighbors that lost their link, one hop per retired node.
+    async fn retire_stale_matching_fields(
+        &self,
+        transaction_id: &ID,
+    ) -> Result<Vec<ConnectedTransaction>>;
+
+    /// The transactions connected to a payload within `traversal`, with the match nodes linking
+    /// them as edges.
+    async fn get_transaction_graph(
+        &self,
+        payload_number: &str,
+        traversal: &TraversalConfig,
+    ) -> Result<TransactionGraph>;
+
+    /// Hub nodes and nodes with an analyst override, largest first.
+    async fn list_hubs(&self, limit: i64) -> Result<Vec<HubNode>>;
+
+    /// Sets or clears (`None`) the analyst hub decision of a node. Returns `false` when no node
+    /// has the given matcher and value.
+    async fn set_hub_override(
+        &self,
+        matcher: &str,
+        value: &str,
+        hub_override: Option<bool>,
+    ) -> Result<bool>;
+
     async fn get_scoring_events(
         &self,
         transaction_id: ID,
-    ) -> Result<Vec<ScoringEvent>, Box<dyn Error + Send + Sync>>;
+    ) -> Result<Vec<ScoringEvent>>;
+
+    /// The latest events of the champion and the shadow challenger for the `limit` transactions
+    /// the challenger scored last, for transactions scored by both.
+    async fn get_shadow_event_pairs(
+        &self,
+        champion_id: ObjectId,
+        challenger_id: ObjectId,
+        limit: i64,
+    ) -> Result<Vec<(ScoringEvent, ScoringEvent)>>;
+
+    /// Scoring events matching the filter, newest first.
+    async fn find_scoring_events(
+        &self,
+        filter: &ScoringEventFilter,
+        limit: i64,
+    ) -> Result<Vec<ScoringEvent>>;
 
     async fn label_transactions(
         &self,
@@ -131,7 +200,7 @@ pub trait CommonStorage<ID: Send + Sync + PartialEq>: Send + Sync {
         fraud_category: &String,
         label_source: &LabelSource,
         labeled_by: &String,
-    ) -> Result<(), Box<dyn Error + Send + Sync>>;
+    ) -> Result<()>;
 
     fn default_matcher_config(&self) -> MatcherConfig {
         (80, 50)
@@ -1,18 +1,34 @@
 use crate::model::processible::{ColumnValueTrait, Filter, FilterOperator};
-use crate::model::{ConnectedTransaction, DirectConnection, Feature, FraudLevel, GenericError, LabelSource, MatcherConfig, MatchingField, SchemaVersion, ScoringModelType, ScoringResult};
-use crate::model::mongo_model::{Label, MatchNode, MatchNodeTransaction, ScoringChannel, ScoringEvent, Transaction};
+use crate::error::{Error, Result};
+use crate::fuzzy::FuzzyMatcherRegistry;
+use crate::geo::{EARTH_RADIUS_KM, GeoLocation, GeoMatcherRegistry, haversine_km};
+use crate::model::{ConnectedTransaction, DirectConnection, Feature, FraudLevel, HubNode, LabelSource, MatcherConfig, MatchingField, NeighborSnapshot, NeighborSummary, ScoringEventFilter, TransactionGraph, TransactionGraphEdge, TransactionGraphNode, SchemaVersion, ScoringResult};
+use crate::model::mongo_model::{GeoJsonPoint, GeoPoint, Label, MatchNode, MatchNodeBucket, MatchNodeTransaction, MatchingFieldKey, ScoringChannel, ScoringEvent, Transaction};
 use crate::storage::common::CommonStorage;
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use common::config::{GraphFilterConfig, HubConfig, HubMode, MatchNodeStorageConfig, TraversalConfig};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use futures::stream::TryStreamExt;
 use mongodb::bson::oid::ObjectId;
 use mongodb::bson::{self, Bson, DateTime as BsonDateTime, Document, doc, to_bson};
-use mongodb::{Client, Collection, Database};
+use mongodb::options::{IndexOptions, ReturnDocument};
+use mongodb::{Client, Collection, Database, IndexModel};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use futures::StreamExt;
 use tracing::debug;
 
+pub const TRANSACTIONS_COLLECTION: &str = "transactions";
+pub const SCORING_EVENTS_COLLECTION: &str = "scoring_events";
+pub const MODEL_ACTIVATIONS_COLLECTION: &str = "model_activations";
+pub const MATCH_NODES_COLLECTION: &str = "match_nodes";
+pub const MATCH_NODE_TRANSACTIONS_COLLECTION: &str = "match_node_transactions";
+pub const GEO_POINTS_COLLECTION: &str = "geo_points";
+pub const SHADOW_SCORING_EVENTS_COLLECTION: &str = "shadow_scoring_events";
+const MATCH_NODE_VALUE_INDEX: &str = "matcher_1_value_1";
+/// Edges emitted for one shared node of a transaction graph.
+const MAX_GRAPH_EDGES_PER_NODE: usize = 100;
+
+
 fn bson_datetime(dt: DateTime<Utc>) -> BsonDateTime {
     BsonDateTime::from_millis(dt.timestamp_millis())
 }
@@ -22,6 +38,10 @@ pub struct MongoCommonStorage {
     pub client: Client,
     pub database: Database,
     pub matcher_configs: HashMap<String, MatcherConfig>,
+    pub hub_config: HubConfig,
+    pub match_node_storage: MatchNodeStorageConfig,
+    pub fuzzy_matchers: FuzzyMatcherRegistry,
+    pub geo_matchers: GeoMatcherRegistry,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,7 +52,67 @@ struct GraphNode {
     #[serde(default)]
     payload_numbers: Vec<String>,
     #[serde(default)]
+    value: String,
+    #[serde(default)]
     depth: i32,
+    #[serde(default)]
+    is_hub: bool,
+    #[serde(default)]
+    hub_override: Option<bool>,
+    /// Some links live in overflow buckets and are not covered by link filters in the pipeline.
+    #[serde(default)]
+    overflow: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct NeighborSummaryDoc {
+    _id: ObjectId,
+    payload_number: String,
+    created_at: NaiveDateTime,
+    #[serde(default)]
+    first_created_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    label: Option<Label>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct NeighborSnapshotDoc {
+    payload_number: String,
+    created_at: NaiveDateTime,
+    #[serde(default)]
+    first_created_at: Option<NaiveDateTime>,
+    #[serde(default)]
+    features_set: Option<SimpleFeaturesDoc>,
+    #[serde(default)]
+    label: Option<Label>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct EdgeNodeDoc {
+    matcher: String,
+    value: String,
+    confidence: i32,
+    payload_numbers: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GraphTransactionDoc {
+    _id: ObjectId,
+    payload_number: String,
+    #[serde(default)]
+    label: Option<Label>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct TransactionScoreDoc {
+    _id: ObjectId,
+    score: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct SimpleFeaturesDoc {
+    #[serde(default)]
+    simple_features: Vec<Feature>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -42,14 +122,43 @@ struct AggregatedGraph {
     #[serde(default)]
     payload_numbers: Vec<String>,
     matcher: String,
+    #[serde(default)]
+    value: String,
     confidence: i32,
+    #[serde(default)]
+    is_hub: bool,
+    #[serde(default)]
+    hub_override: Option<bool>,
+    #[serde(default)]
+    overflow: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MatchingFieldsDoc {
+    #[serde(default)]
+    matching_fields: Option<Vec<MatchingFieldKey>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct InlineLinksDoc {
+    #[serde(default)]
+    transaction_data: Vec<MatchNodeTransaction>,
+}
+
+/// Payload numbers of the bucketed links of a node that pass the link filters.
+#[derive(Debug, Clone, Deserialize)]
+struct BucketLinks {
+    matcher: String,
+    value: String,
+    #[serde(default)]
+    payload_numbers: Vec<String>,
 }
 
 impl MongoCommonStorage {
     async fn get_connection(
         conn_str: &str,
         db_name: &str,
-    ) -> Result<(Client, Database), GenericError> {
+    ) -> Result<(Client, Database)> {
         println!("trying to connect to mongo: {}", conn_str);
         let client = Client::with_uri_str(conn_str).await?;
         let database = client.database(db_name);
@@ -60,27 +169,123 @@ impl MongoCommonStorage {
     pub async fn new(
         conn_str: &str,
         db_name: &str,
-    ) -> Result<Self, GenericError> {
-        let (client, database) = Self::get_connection(conn_str, db_name).await?;
-
-        Ok(Self {
-            client,
-            database,
-            matcher_configs: Self::default_configs(),
-        })
+    ) -> Result<Self> {
+        Self::with_configs(conn_str, db_name, Self::default_configs()).await
     }
 
     pub async fn with_configs(
         conn_str: &str,
         db_name: &str,
         matcher_configs: HashMap<String, MatcherConfig>,
-    ) -> Result<Self, GenericError> {
+    ) -> Result<Self> {
         let (client, database) = Self::get_connection(conn_str, db_name).await?;
-        Ok(Self {
+        let storage = Self {
             client,
             database,
             matcher_configs,
-        })
+            hub_config: HubConfig::default(),
+            match_node_storage: MatchNodeStorageConfig::default(),
+            fuzzy_matchers: FuzzyMatcherRegistry::default(),
+            geo_matchers: GeoMatcherRegistry::default(),
+        };
+        storage.ensure_indexes().await?;
+        Ok(storage)
+    }
+
+    pub fn with_hub_config(mut self, hub_config: HubConfig) -> Self {
+        self.hub_config = hub_config;
+        self
+    }
+
+    pub fn with_match_node_storage_config(mut self, match_node_storage: MatchNodeStorageConfig) -> Self {
+        self.match_node_storage = match_node_storage;
+        self
+    }
+
+    /// Registers fuzzy matchers, their confidence and importance come with them.
+    pub fn with_fuzzy_matchers(mut self, fuzzy_matchers: FuzzyMatcherRegistry) -> Self {
+        self.matcher_configs.extend(fuzzy_matchers.matcher_configs());
+        self.fuzzy_matchers = fuzzy_matchers;
+        self
+    }
+
+    pub fn with_geo_matchers(mut self, geo_matchers: GeoMatcherRegistry) -> Self {
+        self.geo_matchers = geo_matchers;
+        self
+    }
+
+    /// Creates the indexes graph traversal relies on, mirrors
+    /// `migrations/20260119_match_node_indexes.js` followed by
+    /// `migrations/20261016_unique_match_node_values.js`. Existing indexes are left untouched.
+    pub async fn ensure_indexes(&self) -> Result<()> {
+        self.check_match_node_value_index().await?;
+        let match_node_indexes = [
+            doc! { "payload_numbers": 1, "confidence": -1 },
+            doc! { "transaction_data.payload_number": 1, "confidence": -1 },
+            doc! { "transaction_data.datetime_alpha": 1 },
+            doc! { "is_hub": 1, "cardinality": -1 },
+        ]
+        .into_iter()
+        .map(|keys| IndexModel::builder().keys(keys).build())
+        .chain(std::iter::once(
+            IndexModel::builder()
+                .keys(doc! { "matcher": 1, "value": 1 })
+                .options(IndexOptions::builder().unique(true).build())
+                .build(),
+        ));
+
+        self.match_nodes().create_indexes(match_node_indexes).await?;
+
+        let bucket_indexes = [
+            doc! { "matcher": 1, "value": 1, "count": 1 },
+            doc! { "transaction_data.payload_number": 1 },
+        ]
+        .into_iter()
+        .map(|keys| IndexModel::builder().keys(keys).build());
+        self.match_node_transactions().create_indexes(bucket_indexes).await?;
+
+        let geo_indexes = [doc! { "location": "2dsphere" }, doc! { "payload_number": 1, "matcher": 1 }]
+            .into_iter()
+            .map(|keys| IndexModel::builder().keys(keys).build());
+        self.geo_points().create_indexes(geo_indexes).await?;
+
+        let scoring_event_indexes = [doc! { "transaction_id": 1 }, doc! { "decision": 1, "created_at": -1 }]
+            .into_iter()
+            .map(|keys| IndexModel::builder().keys(keys).build());
+        self.scoring_events().create_indexes(scoring_event_indexes).await?;
+        self.shadow_scoring_events()
+            .create_index(IndexModel::builder().keys(doc! { "channel_id": 1, "created_at": -1 }).build())
+            .await?;
+        debug!("Ensured indexes on {}", MATCH_NODES_COLLECTION);
+        Ok(())
+    }
+
+    /// The plain `(matcher, value)` index of older databases has the name of the unique one, so
+    /// creating it would fail with an options conflict. Converting it needs the duplicate nodes
+    /// merged first, which is left to the migration.
+    async fn check_match_node_value_index(&self) -> Result<()> {
+        let collections = self
+            .database
+            .list_collection_names()
+            .filter(doc! { "name": MATCH_NODES_COLLECTION })
+            .await?;
+        if collections.is_empty() {
+            return Ok(());
+        }
+
+        let indexes: Vec<IndexModel> = self.match_nodes().list_indexes().await?.try_collect().await?;
+        let plain = indexes.iter().any(|index| {
+            index.options.as_ref().is_some_and(|options| {
+                options.name.as_deref() == Some(MATCH_NODE_VALUE_INDEX) && options.unique != Some(true)
+            })
+        });
+        if plain {
+            return Err(Error::PendingMigration(format!(
+                "{} has a non-unique {} index, apply migrations/20261016_unique_match_node_values.js",
+                MATCH_NODES_COLLECTION, MATCH_NODE_VALUE_INDEX
+            )));
+        }
+        Ok(())
     }
 
     fn default_configs() -> HashMap<String, MatcherConfig> {
@@ -94,23 +299,31 @@ impl MongoCommonStorage {
     }
 
     fn transactions(&self) -> Collection<Transaction> {
-        self.database.collection("transactions")
+        self.database.collection(TRANSACTIONS_COLLECTION)
     }
 
     fn scoring_events(&self) -> Collection<ScoringEvent> {
-        self.database.collection("scoring_events")
+        self.database.collection(SCORING_EVENTS_COLLECTION)
+    }
+
+    fn shadow_scoring_events(&self) -> Collection<ScoringEvent> {
+        self.database.collection(SHADOW_SCORING_EVENTS_COLLECTION)
     }
 
     fn model_activations(&self) -> Collection<ScoringChannel> {
-        self.database.collection("model_activations")
+        self.database.collection(MODEL_ACTIVATIONS_COLLECTION)
     }
 
     fn match_nodes(&self) -> Collection<MatchNode> {
-        self.database.collection("match_node")
+        self.database.collection(MATCH_NODES_COLLECTION)
     }
 
-    fn match_node_transactions(&self) -> Collection<MatchNodeTransaction> {
-        self.database.collection("match_node_transactions")
+    fn geo_points(&self) -> Collection<GeoPoint> {
+        self.database.collection(GEO_POINTS_COLLECTION)
+    }
+
+    fn match_node_transactions(&self) -> Collection<MatchNodeBucket> {
+        self.database.collection(MATCH_NODE_TRANSACTIONS_COLLECTION)
     }
 
     fn build_filter_document(
@@ -174,7 +387,7 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
         payload_number: String,
         payload: serde_json::Value,
         schema_version: SchemaVersion,
-    ) -> Result<ObjectId, GenericError> {
+    ) -> Result<ObjectId> {
         let now = Utc::now().naive_utc();
 
         let existing = self
@@ -197,6 +410,7 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
             .unwrap_or(1);
         let carried_label = existing.as_ref().and_then(|t| t.label.clone());
         let carried_comment = existing.as_ref().and_then(|t| t.comment.clone());
+        let first_created_at = existing.as_ref().map(|t| t.order_time()).unwrap_or(now);
 
         let doc = Transaction {
             _id: ObjectId::new(),
@@ -211,6 +425,8 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
             last_scoring_date: None,
             features_set: None,
             processing_complete: false,
+            matching_fields: None,
+            first_created_at: Some(first_created_at),
             created_at: now,
             updated_at: now,
         };
@@ -222,19 +438,125 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
     async fn get_transaction(
         &self,
         transaction_id: ObjectId,
-    ) -> Result<Transaction, GenericError> {
+    ) -> Result<Transaction> {
         let model = self
             .transactions()
             .find_one(doc! { "_id": transaction_id })
             .await?
-            .ok_or_else(|| format!("Transaction not found: {}", transaction_id))?;
+            .ok_or_else(|| Error::NotFound(format!("transaction {}", transaction_id)))?;
         Ok(model)
     }
 
+    async fn get_latest_transaction_ids(
+        &self,
+        payload_numbers: &[String],
+    ) -> Result<Vec<ObjectId>> {
+        if payload_numbers.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let ids = self
+            .transactions()
+            .find(doc! { "payload_number": { "$in": payload_numbers }, "is_latest": true })
+            .await?
+            .map_ok(|tx| tx._id)
+            .try_collect()
+            .await?;
+        Ok(ids)
+    }
+
+    async fn get_latest_neighbor_summaries(
+        &self,
+        payload_numbers: &[String],
+    ) -> Result<Vec<NeighborSummary<ObjectId>>> {
+        if payload_numbers.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let summaries = self
+            .transactions()
+            .clone_with_type::<NeighborSummaryDoc>()
+            .find(doc! { "payload_number": { "$in": payload_numbers }, "is_latest": true })
+            .projection(doc! { "_id": 1, "payload_number": 1, "created_at": 1, "first_created_at": 1, "label": 1 })
+            .await?
+            .map_ok(|summary| NeighborSummary {
+                id: summary._id,
+                payload_number: summary.payload_number,
+                created_at: summary.first_created_at.unwrap_or(summary.created_at),
+                is_labeled: summary.label.is_some(),
+            })
+            .try_collect()
+            .await?;
+        Ok(summaries)
+    }
+
+    async fn get_neighbor_snapshots(
+        &self,
+        payload_numbers: &[String],
+        feature_names: &[String],
+    ) -> Result<Vec<NeighborSnapshot>> {
+        if payload_numbers.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut projection =
+            doc! { "_id": 0, "payload_number": 1, "created_at": 1, "first_created_at": 1, "label": 1 };
+        if !feature_names.is_empty() {
+            projection.insert("features_set.simple_features", 1);
+        }
+        let snapshots = self
+            .transactions()
+            .clone_with_type::<NeighborSnapshotDoc>()
+            .find(doc! { "payload_number": { "$in": payload_numbers }, "is_latest": true })
+            .projection(projection)
+            .await?
+            .map_ok(|snapshot| NeighborSnapshot {
+                payload_number: snapshot.payload_number,
+                created_at: snapshot.first_created_at.unwrap_or(snapshot.created_at),
+                features: snapshot
+                    .features_set
+                    .map(|set| set.simple_features)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|feature| feature_names.contains(&feature.name))
+                    .collect(),
+                label: snapshot.label,
+            })
+            .try_collect()
+            .await?;
+        Ok(snapshots)
+    }
+
+    async fn find_unprocessed_transaction_ids(
+        &self,
+        created_before: NaiveDateTime,
+        after: Option<ObjectId>,
+        limit: i64,
+    ) -> Result<Vec<ObjectId>> {
+        let mut query = doc! {
+            "processing_complete": false,
+            "is_latest": true,
+            "created_at": { "$lt": to_bson(&created_before)? },
+        };
+        if let Some(after) = after {
+            query.insert("_id", doc! { "$gt": after });
+        }
+        let ids = self
+            .transactions()
+            .find(query)
+            .sort(doc! { "_id": 1 })
+            .limit(limit.max(1))
+            .await?
+            .map_ok(|tx| tx._id)
+            .try_collect()
+            .await?;
+        Ok(ids)
+    }
+
     async fn filter_transactions(
         &self,
         filters: &[Filter<Box<dyn ColumnValueTrait>>],
-    ) -> Result<Vec<Transaction>, GenericError> {
+    ) -> Result<Vec<Transaction>> {
         let filter_doc = Self::build_filter_document(filters);
         let mut cursor = self.transactions().find(filter_doc).await?;
         let mut transactions = Vec::new();
@@ -247,7 +569,7 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
     async fn mark_transaction_processed(
         &self,
         transaction_id: ObjectId,
-    ) -> Result<(), GenericError> {
+    ) -> Result<()> {
         let now = Utc::now();
         self.transactions()
             .update_one(
@@ -266,10 +588,11 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
 
     async fn get_active_model_activations(
         &self,
-    ) -> Result<Vec<ScoringChannel>, GenericError> {
+    ) -> Result<Vec<ScoringChannel>> {
         let activations: Vec<ScoringChannel> = self
             .model_activations()
             .find(doc! { "is_active": true })
+            .sort(doc! { "_id": 1 })
             .await?
             .try_collect()
             .await?;
@@ -281,33 +604,23 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
         transaction_id: ObjectId,
         channel: ScoringChannel,
         scoring_result: Box<dyn ScoringResult>,
-    ) -> Result<(), GenericError> {
+    ) -> Result<()> {
         let now = Utc::now().naive_utc();
 
-        match channel.model.model_type {
-            ScoringModelType::ExpressionBased => {
-                let triggered_rules: Vec<String> = scoring_result
-                    .get_result_payload()
-                    .as_array()
-                    .expect("Scoring result is not an array while model type is ExpressionBased")
-                    .iter()
-                    .map(|rule| rule.as_str().expect("Rule is not a string").to_string())
-                    .collect::<Vec<String>>();
-                
-                let scoring_doc = ScoringEvent {
-                    _id: ObjectId::new(),
-                    transaction_id,
-                    channel_id: channel._id,
-                    triggered_rules,
-                    created_at: now,
-                };
-                self.scoring_events().insert_one(scoring_doc).await?;
-            }
-            _ => {
-                return Err(format!("Unsupported model type: {:?}", channel.model.model_type).into());
-            }
-        }
-
+        let total_score = scoring_result.get_total_score();
+        let scoring_doc = ScoringEvent {
+            _id: ObjectId::new(),
+            transaction_id,
+            channel_id: channel._id,
+            channel_version: channel.version,
+            triggered_rules: scoring_result.get_triggered_rules(),
+            total_score,
+            rule_scores: scoring_result.get_rule_scores(),
+            decision: channel.thresholds.as_ref().map(|thresholds| thresholds.decide(total_score)),
+            created_at: now,
+        };
+        let events = if channel.shadow { self.shadow_scoring_events() } else { self.scoring_events() };
+        events.insert_one(scoring_doc).await?;
 
         Ok(())
     }
@@ -317,7 +630,7 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
         transaction_id: ObjectId,
         simple_features: &'a Option<&'a [Feature]>,
         graph_features: &'a [Feature],
-    ) -> Result<(), GenericError> {
+    ) -> Result<()> {
         self.validate_features(graph_features)?;
         if let Some(features) = simple_features {
             self.validate_features(features)?;
@@ -344,44 +657,99 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
     async fn find_connected_transactions(
         &self,
         payload_number: &str,
-        max_depth: Option<i32>,
-        limit_count: Option<i32>,
-        _filter_config: Option<serde_json::Value>,
-        min_confidence: Option<i32>,
-    ) -> Result<Vec<ConnectedTransaction>, GenericError> {
-        let max_depth = max_depth.unwrap_or(10).max(1);
-        let graph_depth = (max_depth - 1).max(0);
-        let min_confidence = min_confidence.unwrap_or(0).clamp(0, 100);
-
-        let pipeline = vec![
-            doc! { "$match": { "transaction_data.payload_number": payload_number, "confidence": { "$gte": min_confidence } } },
-            doc! { "$addFields": { "payload_numbers": "$payload_numbers" } },
+        traversal: &TraversalConfig,
+        filter_config: Option<&GraphFilterConfig>,
+    ) -> Result<Vec<ConnectedTransaction>> {
+        let graph_depth = (traversal.max_depth.max(1) - 1).max(0);
+        let filter = filter_config.cloned().unwrap_or_default();
+
+        // Matcher and confidence limits are static, so they also prune the traversal itself
+        let mut node_conditions = vec![Self::confidence_condition(traversal)];
+        if let Some(matcher_condition) = Self::matcher_condition(&filter) {
+            node_conditions.push(doc! { "matcher": matcher_condition });
+        }
+        if self.hub_config.mode == HubMode::Exclude {
+            node_conditions.push(Self::not_hub_condition());
+        }
+        // Blocking keys only name candidates, they are scored as direct connections instead
+        if !self.fuzzy_matchers.is_empty() {
+            node_conditions.push(doc! { "matcher": { "$nin": self.fuzzy_matchers.names() } });
+        }
+        let root_match = doc! { "$and": [
+            { "payload_numbers": payload_number },
+            { "$and": node_conditions.clone() },
+        ]};
+        let restrict_match = doc! { "$and": [
+            { "payload_numbers": { "$ne": payload_number } },
+            { "$and": node_conditions },
+        ]};
+
+        let mut pipeline = vec![
+            doc! { "$match": root_match },
             doc! { "$graphLookup": {
-                "from": "match_nodes",
+                "from": MATCH_NODES_COLLECTION,
                 "startWith": "$payload_numbers",
                 "connectFromField": "payload_numbers",
                 "connectToField": "payload_numbers",
                 "as": "graph",
                 "maxDepth": graph_depth,
                 "depthField": "depth",
-                "restrictSearchWithMatch": {
-                    "payload_numbers": { "$ne": payload_number },
-                    "confidence": { "$gte": min_confidence },
-                }
-            }},
-            doc! { "$project": {
-                "_id": 0,
-                "matcher": 1,
-                "confidence": 1,
-                "payload_numbers": 1,
-                "graph": {
-                    "matcher": "$graph.matcher",
-                    "confidence": "$graph.confidence",
-                    "payload_numbers": "$graph.payload_numbers",
-                    "depth": "$graph.depth"
-                }
+                "restrictSearchWithMatch": restrict_match,
             }},
         ];
+        if let Some(depth_condition) = Self::matcher_depth_condition(traversal) {
+            pipeline.push(doc! { "$addFields": {
+                "graph": { "$filter": { "input": "$graph", "as": "node", "cond": depth_condition } },
+            }});
+        }
+
+        let root_link = if filter.max_age_seconds.is_some()
+            || filter.root_time_window_seconds.is_some()
+            || filter.max_distance_km.is_some()
+        {
+            self.find_root_link(payload_number).await?
+        } else {
+            None
+        };
+        let link_condition = Self::link_condition(&filter, root_link.as_ref())?;
+        if let Some(link_condition) = &link_condition {
+            pipeline.push(doc! { "$addFields": {
+                "payload_numbers": Self::linked_payload_numbers("$transaction_data", link_condition),
+                "overflow": Self::has_overflow("$link_count", "$transaction_data"),
+                "graph": { "$map": {
+                    "input": "$graph",
+                    "as": "node",
+                    "in": { "$mergeObjects": [
+                        "$$node",
+                        {
+                            "payload_numbers": Self::linked_payload_numbers("$$node.transaction_data", link_condition),
+                            "overflow": Self::has_overflow("$$node.link_count", "$$node.transaction_data"),
+                        },
+                    ]},
+                }},
+            }});
+        }
+
+        // Inclusion keeps the fields of every graph element, "$graph.<field>" would put the
+        // whole array into each of them
+        pipeline.push(doc! { "$project": {
+            "_id": 0,
+            "matcher": 1,
+            "value": 1,
+            "confidence": 1,
+            "payload_numbers": 1,
+            "is_hub": 1,
+            "hub_override": 1,
+            "overflow": 1,
+            "graph.matcher": 1,
+            "graph.value": 1,
+            "graph.confidence": 1,
+            "graph.payload_numbers": 1,
+            "graph.depth": 1,
+            "graph.is_hub": 1,
+            "graph.hub_override": 1,
+            "graph.overflow": 1,
+        }});
 
         let agg_docs: Vec<Document> = self
             .match_nodes()
@@ -395,16 +763,30 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
             agg_results.push(bson::from_document::<AggregatedGraph>(doc)?);
         }
 
-        Ok(Self::collect_connections(payload_number, agg_results, limit_count))
+        if let Some(link_condition) = &link_condition {
+            let bucket_links = self.find_bucket_links(&agg_results, link_condition).await?;
+            Self::merge_bucket_links(&mut agg_results, bucket_links);
+        }
+
+        if self.hub_config.mode == HubMode::DownWeight {
+            agg_results = Self::down_weight_hubs(agg_results, self.hub_config.down_weight, traversal);
+        }
+
+        Ok(Self::collect_connections(payload_number, agg_results, Some(traversal.limit.max(0))))
     }
 
     async fn get_direct_connections(
         &self,
         payload_number: &str,
-    ) -> Result<Vec<DirectConnection>, GenericError> {
+    ) -> Result<Vec<DirectConnection>> {
+        let mut query = doc! { "payload_numbers": payload_number };
+        if self.hub_config.mode == HubMode::Exclude {
+            query.extend(Self::not_hub_condition());
+        }
         let nodes = self
             .match_nodes()
-            .find(doc! { "payload_numbers": payload_number })
+            .find(query)
+            .projection(doc! { "transaction_data": 0 })
             .await?
             .try_collect::<Vec<MatchNode>>()
             .await?;
@@ -413,22 +795,54 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
         let mut res = Vec::new();
 
         for node in nodes {
+            let confidence = if node.is_effective_hub() {
+                Self::weighted_confidence(node.confidence, self.hub_config.down_weight)
+            } else {
+                node.confidence
+            };
+            // Fuzzy links are scored by the similarity of the source values kept on the links
+            let fuzzy = match self.fuzzy_matchers.get(&node.matcher) {
+                // Reading and comparing every member of a common key costs O(N) per transaction
+                Some(matcher) if matcher.is_oversized_block(node.cardinality) => continue,
+                Some(matcher) => Some((matcher, self.find_source_values(&node).await?)),
+                None => None,
+            };
             for other in node.payload_numbers.iter() {
                 if other == payload_number {
                     continue;
                 }
+                let confidence = match &fuzzy {
+                    Some((matcher, values)) => {
+                        let (Some(own), Some(theirs)) = (values.get(payload_number), values.get(other)) else {
+                            continue;
+                        };
+                        match matcher.scaled_confidence(confidence, own, theirs) {
+                            Some(confidence) => confidence,
+                            None => continue,
+                        }
+                    }
+                    None => confidence,
+                };
                 let key = format!("{}::{}", other, node.matcher);
                 if seen.insert(key) {
                     res.push(DirectConnection {
                         payload_number: other.clone(),
                         matcher: node.matcher.clone(),
-                        confidence: node.confidence,
+                        confidence,
                         importance: node.importance,
+                        distance_km: None,
+                        label: None,
                     });
                 }
             }
         }
 
+        for connection in self.find_geo_connections(payload_number).await? {
+            if seen.insert(format!("{}::{}", connection.payload_number, connection.matcher)) {
+                res.push(connection);
+            }
+        }
+
         Ok(res)
     }
 
@@ -446,71 +860,85 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
         lat_gamma: Option<f64>,
         long_delta: Option<f64>,
         lat_delta: Option<f64>,
-    ) -> Result<(), GenericError> {
-        if matching_fields.is_empty() {
-            return Ok(());
-        }
-
+    ) -> Result<()> {
         let payload_number = self
             .transactions()
             .find_one(doc! { "_id": transaction_id })
             .await?
             .map(|t| t.payload_number)
-            .ok_or_else(|| format!("transaction {} not found", transaction_id))?;
+            .ok_or_else(|| Error::NotFound(format!("transaction {}", transaction_id)))?;
+
+        // Kept per version, so the next version can retire the nodes it no longer links to
+        let keys: Vec<MatchingFieldKey> = matching_fields
+            .iter()
+            .map(|field| MatchingFieldKey { matcher: field.matcher.clone(), value: field.value.clone() })
+            .collect();
+        self.transactions()
+            .update_one(
+                doc! { "_id": transaction_id },
+                doc! { "$set": { "matching_fields": to_bson(&keys)? } },
+            )
+            .await?;
 
         for field in matching_fields {
             let (conf, imp) = self
                 .matcher_configs
                 .get(&field.matcher)
                 .cloned()
-                .expect("Matcher config not found");
-
-            let mut maybeNode = self
-                .match_nodes()
-                .find_one(doc! { "matcher": &field.matcher, "value": &field.value})
-                .await?;
+                .ok_or_else(|| Error::MissingMatcherConfig(field.matcher.clone()))?;
 
+            // Explicit values win over the ones extracted with the field
             let mnt = MatchNodeTransaction {
                 payload_number: payload_number.clone(),
-                datetime_alpha: datetime_alpha.map(|dt| dt.naive_utc()),
-                datetime_beta: datetime_beta.map(|dt| dt.naive_utc()),
-                long_alpha,
-                lat_alpha,
-                long_beta,
-                lat_beta,
-                long_gamma,
-                lat_gamma,
-                long_delta,
-                lat_delta,
+                raw_value: field.raw_value.clone(),
+                source_value: field.source_value.clone(),
+                datetime_alpha: datetime_alpha.or(field.datetime_alpha).map(|dt| dt.naive_utc()),
+                datetime_beta: datetime_beta.or(field.datetime_beta).map(|dt| dt.naive_utc()),
+                long_alpha: long_alpha.or(field.long_alpha),
+                lat_alpha: lat_alpha.or(field.lat_alpha),
+                long_beta: long_beta.or(field.long_beta),
+                lat_beta: lat_beta.or(field.lat_beta),
+                long_gamma: long_gamma.or(field.long_gamma),
+                lat_gamma: lat_gamma.or(field.lat_gamma),
+                long_delta: long_delta.or(field.long_delta),
+                lat_delta: lat_delta.or(field.lat_delta),
                 created_at: Utc::now().naive_utc(),
             };
 
-            if let Some(mut node) = maybeNode {
-                node.transaction_data.push(mnt);
-                if !node.payload_numbers.contains(&payload_number) {
-                    node.payload_numbers.push(payload_number.clone());
-                }
-                self.match_nodes().update_one(
-                    doc! { "_id": node._id },
+            let filter = doc! { "matcher": &field.matcher, "value": &field.value };
+            let inline_links = self.match_node_storage.inline_links.max(1);
+            // A single upsert per node, so concurrent writers can't duplicate it or lose pushes.
+            // Equality on the unique (matcher, value) index makes the server retry racing inserts.
+            let before = self
+                .match_nodes()
+                .clone_with_type::<InlineLinksDoc>()
+                .find_one_and_update(
+                    filter.clone(),
                     doc! {
-                        "$set": {
-                            "transaction_data": to_bson(&node.transaction_data)?,
-                            "payload_numbers": to_bson(&node.payload_numbers)?,
-                        }
-                    }
-                ).await?;
-            } else {
-                let new_node = MatchNode {
-                    _id: ObjectId::new(),
-                    transaction_data: vec![mnt],
-                    payload_numbers: vec![payload_number.clone()],
-                    matcher: field.matcher.clone(),
-                    value: field.value.clone(),
-                    confidence: conf,
-                    importance: imp,
-                };
-                self.match_nodes().insert_one(new_node).await?;
+                        "$push": { "transaction_data": {
+                            "$each": [to_bson(&mnt)?],
+                            "$slice": -(inline_links as i64),
+                        }},
+                        "$addToSet": { "payload_numbers": &payload_number },
+                        "$inc": { "link_count": 1_i64 },
+                        "$setOnInsert": { "confidence": conf, "importance": imp },
+                    },
+                )
+                .upsert(true)
+                .return_document(ReturnDocument::Before)
+                .projection(doc! { "_id": 0, "transaction_data": 1 })
+                .await?;
+            // Each writer sees its own pre-image, so it moves exactly the links its push evicted
+            let evicted = Self::evicted_links(before.map(|b| b.transaction_data).unwrap_or_default(), inline_links);
+            if !evicted.is_empty() {
+                self.save_overflow_links(&field.matcher, &field.value, evicted).await?;
             }
+            self.refresh_cardinality(filter).await?;
+        }
+
+        if !self.geo_matchers.is_empty() {
+            let locations = self.geo_matchers.locations(matching_fields, long_alpha.zip(lat_alpha));
+            self.save_geo_points(&payload_number, &locations).await?;
         }
 
         debug!(
@@ -521,10 +949,177 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
         Ok(())
     }
 
+    async fn retire_stale_matching_fields(
+        &self,
+        transaction_id: &ObjectId,
+    ) -> Result<Vec<ConnectedTransaction>> {
+        let transaction = self.get_transaction(*transaction_id).await?;
+        let current: HashSet<MatchingFieldKey> =
+            transaction.matching_fields.unwrap_or_default().into_iter().collect();
+        let previous = self
+            .transactions()
+            .clone_with_type::<MatchingFieldsDoc>()
+            .find_one(doc! {
+                "payload_number": &transaction.payload_number,
+                "transaction_version": { "$lt": transaction.transaction_version },
+                "matching_fields": { "$type": "array" },
+            })
+            .sort(doc! { "transaction_version": -1 })
+            .projection(doc! { "_id": 0, "matching_fields": 1 })
+            .await?;
+        let Some(previous) = previous.and_then(|doc| doc.matching_fields) else {
+            return Ok(Vec::new());
+        };
+
+        let mut unlinked = Vec::new();
+        for key in previous.into_iter().filter(|key| !current.contains(key)) {
+            let filter = doc! { "matcher": &key.matcher, "value": &key.value };
+            let node = self
+                .match_nodes()
+                .clone_with_type::<Document>()
+                .find_one_and_update(
+                    filter.clone(),
+                    doc! { "$pull": {
+                        "payload_numbers": &transaction.payload_number,
+                        "transaction_data": { "payload_number": &transaction.payload_number },
+                    }},
+                )
+                .return_document(ReturnDocument::Before)
+                .projection(doc! { "transaction_data": 0 })
+                .await?;
+            let Some(node) = node else {
+                continue;
+            };
+            self.match_node_transactions()
+                .update_many(
+                    filter.clone(),
+                    doc! { "$pull": { "transaction_data": { "payload_number": &transaction.payload_number } } },
+                )
+                .await?;
+            self.match_node_transactions()
+                .update_many(filter.clone(), vec![doc! { "$set": { "count": { "$toLong": { "$size": "$transaction_data" } } } }])
+                .await?;
+            self.refresh_cardinality(filter.clone()).await?;
+            self.delete_if_unlinked(filter).await?;
+
+            let node: MatchNode = bson::from_document(node)?;
+            // Excluded hubs are not traversed, losing one of their links changes nothing for the
+            // neighbors; down-weighted hubs still connect them
+            if self.hub_config.mode == HubMode::Exclude && node.is_effective_hub() {
+                continue;
+            }
+            unlinked.extend(
+                node.payload_numbers
+                    .into_iter()
+                    .filter(|payload_number| *payload_number != transaction.payload_number)
+                    .map(|payload_number| ConnectedTransaction {
+                        payload_number,
+                        path: vec![node.matcher.clone()],
+                        total_confidence: node.confidence,
+                        label: None,
+                    }),
+            );
+        }
+
+        debug!(
+            "Retired stale matching fields of transaction {}, {} links removed",
+            transaction_id,
+            unlinked.len()
+        );
+        Ok(unlinked)
+    }
+
+    async fn get_transaction_graph(
+        &self,
+        payload_number: &str,
+        traversal: &TraversalConfig,
+    ) -> Result<TransactionGraph> {
+        let connected = self.find_connected_transactions(payload_number, traversal, None).await?;
+        let mut depths: HashMap<String, i32> = HashMap::from([(payload_number.to_string(), 0)]);
+        for ct in connected {
+            depths.insert(ct.payload_number, ct.path.len() as i32);
+        }
+        let members: Vec<&String> = depths.keys().collect();
+
+        // Edges follow the nodes the traversal may walk
+        let mut node_conditions = vec![Self::confidence_condition(traversal)];
+        if self.hub_config.mode == HubMode::Exclude {
+            node_conditions.push(Self::not_hub_condition());
+        }
+        if !self.fuzzy_matchers.is_empty() {
+            node_conditions.push(doc! { "matcher": { "$nin": self.fuzzy_matchers.names() } });
+        }
+        let edge_nodes: Vec<EdgeNodeDoc> = self
+            .match_nodes()
+            .clone_with_type::<EdgeNodeDoc>()
+            .find(doc! { "$and": [
+                { "payload_numbers": { "$in": &members } },
+                { "$and": node_conditions },
+            ]})
+            .projection(doc! { "_id": 0, "matcher": 1, "value": 1, "confidence": 1, "payload_numbers": 1 })
+            .await?
+            .try_collect()
+            .await?;
+        let edges = Self::graph_edges(edge_nodes, &depths);
+
+        let versions: Vec<GraphTransactionDoc> = self
+            .transactions()
+            .clone_with_type::<GraphTransactionDoc>()
+            .find(doc! { "payload_number": { "$in": &members }, "is_latest": true })
+            .projection(doc! { "_id": 1, "payload_number": 1, "label": 1 })
+            .await?
+            .try_collect()
+            .await?;
+        let scores = self.latest_scores(&versions).await?;
+
+        let mut nodes: Vec<TransactionGraphNode> = versions
+            .into_iter()
+            .map(|version| TransactionGraphNode {
+                depth: depths.get(&version.payload_number).copied().unwrap_or_default(),
+                score: scores.get(&version._id).copied(),
+                payload_number: version.payload_number,
+                label: version.label,
+            })
+            .collect();
+        nodes.sort_by(|a, b| (a.depth, &a.payload_number).cmp(&(b.depth, &b.payload_number)));
+
+        Ok(TransactionGraph { nodes, edges })
+    }
+
+    async fn list_hubs(&self, limit: i64) -> Result<Vec<HubNode>> {
+        let hubs = self
+            .match_nodes()
+            .clone_with_type::<HubNode>()
+            .find(doc! { "$or": [{ "is_hub": true }, { "hub_override": { "$in": [true, false] } }] })
+            .projection(doc! { "_id": 0, "matcher": 1, "value": 1, "cardinality": 1, "is_hub": 1, "hub_override": 1 })
+            .sort(doc! { "cardinality": -1 })
+            .limit(limit.max(1))
+            .await?
+            .try_collect()
+            .await?;
+        Ok(hubs)
+    }
+
+    async fn set_hub_override(
+        &self,
+        matcher: &str,
+        value: &str,
+        hub_override: Option<bool>,
+    ) -> Result<bool> {
+        let result = self
+            .match_nodes()
+            .update_one(
+                doc! { "matcher": matcher, "value": value },
+                doc! { "$set": { "hub_override": hub_override } },
+            )
+            .await?;
+        Ok(result.matched_count > 0)
+    }
+
     async fn get_scoring_events(
         &self,
         transaction_id: ObjectId,
-    ) -> Result<Vec<ScoringEvent>, GenericError> {
+    ) -> Result<Vec<ScoringEvent>> {
         let mut cursor = self
             .scoring_events()
             .find(doc! { "transaction_id": transaction_id })
@@ -536,30 +1131,118 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
         Ok(events)
     }
 
-    async fn label_transactions(
+    async fn get_shadow_event_pairs(
         &self,
-        payload_numbers: &[String],
-        fraud_level: &FraudLevel,
-        fraud_category: &String,
-        label_source: &LabelSource,
-        labeled_by: &String,
-    ) -> Result<(), GenericError> {
+        champion_id: ObjectId,
+        challenger_id: ObjectId,
+        limit: i64,
+    ) -> Result<Vec<(ScoringEvent, ScoringEvent)>> {
+        // Newest first, so the first event of a transaction is its latest
+        let mut challenger: Vec<ScoringEvent> = Vec::new();
+        let mut cursor = self
+            .shadow_scoring_events()
+            .find(doc! { "channel_id": challenger_id })
+            .sort(doc! { "created_at": -1 })
+            .await?;
+        let mut seen = HashSet::new();
+        while let Some(event) = cursor.try_next().await? {
+            if seen.insert(event.transaction_id) {
+                challenger.push(event);
+                if challenger.len() as i64 >= limit.max(1) {
+                    break;
+                }
+            }
+        }
 
-        let latest = self
-            .transactions()
-            .find(doc! { "payload_number": { "$in": payload_numbers }, "is_latest": true })
+        let transaction_ids: Vec<ObjectId> = challenger.iter().map(|event| event.transaction_id).collect();
+        let mut champion: HashMap<ObjectId, ScoringEvent> = HashMap::new();
+        let mut cursor = self
+            .scoring_events()
+            .find(doc! { "channel_id": champion_id, "transaction_id": { "$in": transaction_ids } })
+            .sort(doc! { "created_at": -1 })
             .await?;
+        while let Some(event) = cursor.try_next().await? {
+            champion.entry(event.transaction_id).or_insert(event);
+        }
 
-        self.transactions()
-            .update_many(
+        Ok(challenger
+            .into_iter()
+            .filter_map(|event| champion.remove(&event.transaction_id).map(|champion| (champion, event)))
+            .collect())
+    }
+
+    async fn find_scoring_events(
+        &self,
+        filter: &ScoringEventFilter,
+        limit: i64,
+    ) -> Result<Vec<ScoringEvent>> {
+        let mut query = doc! {};
+        if let Some(payload_number) = &filter.payload_number {
+            let ids: Vec<ObjectId> = self
+                .transactions()
+                .clone_with_type::<Document>()
+                .find(doc! { "payload_number": payload_number })
+                .projection(doc! { "_id": 1 })
+                .await?
+                .try_filter_map(|tx| async move { Ok(tx.get_object_id("_id").ok()) })
+                .try_collect()
+                .await?;
+            query.insert("transaction_id", doc! { "$in": ids });
+        }
+        if let Some(channel_id) = filter.channel_id {
+            query.insert("channel_id", channel_id);
+        }
+        if let Some(decision) = filter.decision {
+            query.insert("decision", to_bson(&decision)?);
+        }
+        let mut total_score = doc! {};
+        if let Some(min) = filter.min_total_score {
+            total_score.insert("$gte", min);
+        }
+        if let Some(max) = filter.max_total_score {
+            total_score.insert("$lte", max);
+        }
+        if !total_score.is_empty() {
+            query.insert("total_score", total_score);
+        }
+
+        let events = self
+            .scoring_events()
+            .find(query)
+            .sort(doc! { "created_at": -1 })
+            .limit(limit.max(1))
+            .await?
+            .try_collect()
+            .await?;
+        Ok(events)
+    }
+
+    async fn label_transactions(
+        &self,
+        payload_numbers: &[String],
+        fraud_level: &FraudLevel,
+        fraud_category: &String,
+        label_source: &LabelSource,
+        labeled_by: &String,
+    ) -> Result<()> {
+
+        let latest: Vec<Transaction> = self
+            .transactions()
+            .find(doc! { "payload_number": { "$in": payload_numbers }, "is_latest": true })
+            .await?
+            .try_collect()
+            .await?;
+
+        self.transactions()
+            .update_many(
                 doc! { "payload_number": { "$in": payload_numbers }, "is_latest": true},
                 doc! { "$set": { "is_latest": false } },
             )
             .await?;
 
         let now = Utc::now().naive_utc();
-        let new_txs = latest.map(|tx| {
-                let mut new_tx = tx.expect("Transaction cannot be retrieved").clone();
+        let new_txs = latest.into_iter().map(|tx| {
+                let mut new_tx = tx;
                 new_tx._id = ObjectId::new();
                 new_tx.label = Some(Label {
                     fraud_level: *fraud_level,
@@ -572,10 +1255,11 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
                 new_tx.updated_at = now;
                 new_tx
             })
-            .collect::<Vec<_>>()
-            .await;
+            .collect::<Vec<_>>();
 
-        self.transactions().insert_many(new_txs).await?;
+        if !new_txs.is_empty() {
+            self.transactions().insert_many(new_txs).await?;
+        }
 
 
         Ok(())
@@ -583,6 +1267,574 @@ impl CommonStorage<ObjectId> for MongoCommonStorage {
 }
 
 impl MongoCommonStorage {
+    /// One edge per pair of graph members sharing a node. Nodes shared by too many members for
+    /// every pair link them to the member closest to the root instead, at most
+    /// `MAX_GRAPH_EDGES_PER_NODE` edges per node.
+    fn graph_edges(nodes: Vec<EdgeNodeDoc>, members: &HashMap<String, i32>) -> Vec<TransactionGraphEdge> {
+        let mut edges = Vec::new();
+        for node in nodes {
+            let mut linked: Vec<&String> = node
+                .payload_numbers
+                .iter()
+                .filter(|payload_number| members.contains_key(*payload_number))
+                .collect();
+            linked.sort();
+            linked.dedup();
+            let pairs: Vec<(&String, &String)> = if linked.len() * linked.len().saturating_sub(1) / 2
+                <= MAX_GRAPH_EDGES_PER_NODE
+            {
+                linked
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, source)| linked[i + 1..].iter().map(move |target| (*source, *target)))
+                    .collect()
+            } else {
+                let Some(&center) = linked.iter().min_by_key(|payload_number| (members[**payload_number], **payload_number))
+                else {
+                    continue;
+                };
+                linked
+                    .iter()
+                    .filter(|payload_number| **payload_number != center)
+                    .take(MAX_GRAPH_EDGES_PER_NODE)
+                    .map(|other| (center.min(*other), center.max(*other)))
+                    .collect()
+            };
+            edges.extend(pairs.into_iter().map(|(source, target)| TransactionGraphEdge {
+                source: source.clone(),
+                target: target.clone(),
+                matcher: node.matcher.clone(),
+                value: node.value.clone(),
+                confidence: node.confidence,
+            }));
+        }
+        edges.sort_by(|a, b| (&a.source, &a.target, &a.matcher).cmp(&(&b.source, &b.target, &b.matcher)));
+        edges
+    }
+
+    /// The highest total score among the latest events of each live channel, per transaction.
+    async fn latest_scores(&self, versions: &[GraphTransactionDoc]) -> Result<HashMap<ObjectId, i32>> {
+        if versions.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let ids: Vec<ObjectId> = versions.iter().map(|version| version._id).collect();
+        let scores: Vec<TransactionScoreDoc> = self
+            .scoring_events()
+            .clone_with_type::<Document>()
+            .aggregate(Self::latest_scores_pipeline(ids))
+            .with_type::<TransactionScoreDoc>()
+            .await?
+            .try_collect()
+            .await?;
+        Ok(scores.into_iter().map(|score| (score._id, score.score)).collect())
+    }
+
+    fn latest_scores_pipeline(transaction_ids: Vec<ObjectId>) -> Vec<Document> {
+        vec![
+            doc! { "$match": { "transaction_id": { "$in": transaction_ids } } },
+            doc! { "$addFields": { "created_at_date": Self::to_date("$created_at") } },
+            doc! { "$sort": { "created_at_date": -1, "_id": -1 } },
+            doc! { "$group": {
+                "_id": { "transaction_id": "$transaction_id", "channel_id": "$channel_id" },
+                "total_score": { "$first": "$total_score" },
+            }},
+            doc! { "$group": { "_id": "$_id.transaction_id", "score": { "$max": "$total_score" } } },
+        ]
+    }
+
+    /// The most recent link of the root transaction over all of its nodes and buckets, the
+    /// reference point of time and geo filters.
+    async fn find_root_link(&self, payload_number: &str) -> Result<Option<MatchNodeTransaction>> {
+        let root_links = vec![
+            doc! { "$match": { "transaction_data.payload_number": payload_number } },
+            doc! { "$project": { "_id": 0, "transaction_data": 1 } },
+        ];
+        let mut pipeline = root_links.clone();
+        pipeline.extend([
+            doc! { "$unionWith": { "coll": MATCH_NODE_TRANSACTIONS_COLLECTION, "pipeline": root_links } },
+            doc! { "$unwind": "$transaction_data" },
+            doc! { "$replaceRoot": { "newRoot": "$transaction_data" } },
+            doc! { "$match": { "payload_number": payload_number } },
+            doc! { "$addFields": { "created_at_date": Self::to_date("$created_at") } },
+            doc! { "$sort": { "created_at_date": -1 } },
+            doc! { "$limit": 1 },
+        ]);
+
+        let link = self
+            .match_nodes()
+            .aggregate(pipeline)
+            .await?
+            .try_next()
+            .await?;
+        Ok(link.map(bson::from_document::<MatchNodeTransaction>).transpose()?)
+    }
+
+    /// The latest source value each payload number linked a fuzzy node with, inline and bucketed.
+    /// Links written before `source_value` existed kept it in `raw_value`.
+    async fn find_source_values(&self, node: &MatchNode) -> Result<HashMap<String, String>> {
+        let links = doc! {
+            "transaction_data.payload_number": 1,
+            "transaction_data.source_value": 1,
+            "transaction_data.raw_value": 1,
+            "transaction_data.created_at": 1,
+        };
+        let mut linked: Vec<MatchNodeTransaction> = self
+            .match_nodes()
+            .clone_with_type::<InlineLinksDoc>()
+            .find_one(doc! { "_id": node._id })
+            .projection(links.clone())
+            .await?
+            .map(|doc| doc.transaction_data)
+            .unwrap_or_default();
+        if node.link_count > linked.len() as i64 {
+            let buckets: Vec<InlineLinksDoc> = self
+                .match_node_transactions()
+                .clone_with_type::<InlineLinksDoc>()
+                .find(doc! { "matcher": &node.matcher, "value": &node.value })
+                .projection(links)
+                .await?
+                .try_collect()
+                .await?;
+            linked.extend(buckets.into_iter().flat_map(|bucket| bucket.transaction_data));
+        }
+
+        linked.sort_by_key(|link| link.created_at);
+        Ok(linked
+            .into_iter()
+            .filter_map(|link| link.source_value.or(link.raw_value).map(|value| (link.payload_number, value)))
+            .collect())
+    }
+
+    /// Replaces the geo points of a payload, the latest version decides where it is.
+    async fn save_geo_points(&self, payload_number: &str, locations: &[GeoLocation]) -> Result<()> {
+        self.geo_points()
+            .delete_many(doc! { "payload_number": payload_number, "matcher": { "$in": self.geo_matchers.names() } })
+            .await?;
+        if locations.is_empty() {
+            return Ok(());
+        }
+        let now = Utc::now().naive_utc();
+        let points = locations.iter().map(|location| GeoPoint {
+            _id: ObjectId::new(),
+            payload_number: payload_number.to_string(),
+            matcher: location.matcher.clone(),
+            location: GeoJsonPoint::new(location.long, location.lat),
+            created_at: now,
+        });
+        self.geo_points().insert_many(points).await?;
+        Ok(())
+    }
+
+    /// Transactions located within the radius of a geo matcher from any point of the payload,
+    /// closest point per transaction and matcher. Only the `max_neighbors` nearest points of each
+    /// point are considered.
+    async fn find_geo_connections(&self, payload_number: &str) -> Result<Vec<DirectConnection>> {
+        if self.geo_matchers.is_empty() {
+            return Ok(Vec::new());
+        }
+        let own_points: Vec<GeoPoint> = self
+            .geo_points()
+            .find(doc! { "payload_number": payload_number })
+            .await?
+            .try_collect()
+            .await?;
+
+        let mut closest: HashMap<(String, String), f64> = HashMap::new();
+        for point in own_points {
+            let Some(config) = self.geo_matchers.get(&point.matcher) else {
+                continue;
+            };
+            let [long, lat] = point.location.coordinates;
+            let nearby: Vec<GeoPoint> = self
+                .geo_points()
+                .find(doc! {
+                    "matcher": &point.matcher,
+                    "payload_number": { "$ne": payload_number },
+                    "location": { "$nearSphere": {
+                        "$geometry": to_bson(&point.location)?,
+                        "$maxDistance": config.radius_km * 1000.0,
+                    }},
+                })
+                .limit(config.max_neighbors as i64)
+                .await?
+                .try_collect()
+                .await?;
+            for other in nearby {
+                let [other_long, other_lat] = other.location.coordinates;
+                let distance = haversine_km(lat, long, other_lat, other_long);
+                closest
+                    .entry((other.payload_number, other.matcher))
+                    .and_modify(|d| *d = d.min(distance))
+                    .or_insert(distance);
+            }
+        }
+
+        let mut connections: Vec<DirectConnection> = closest
+            .into_iter()
+            .filter_map(|((payload_number, matcher), distance_km)| {
+                let confidence = self.geo_matchers.decayed_confidence(&matcher, distance_km)?;
+                let importance = self.geo_matchers.get(&matcher)?.importance;
+                Some(DirectConnection {
+                    payload_number,
+                    matcher,
+                    confidence,
+                    importance,
+                    distance_km: Some(distance_km),
+                    label: None,
+                })
+            })
+            .collect();
+        connections.sort_by(|a, b| a.distance_km.partial_cmp(&b.distance_km).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(connections)
+    }
+
+    /// Recomputes `cardinality` and `is_hub` from the stored payload numbers, concurrent writers
+    /// converge on the final size. Payload numbers beyond the cap are dropped again, the node is
+    /// a hub from then on, so it neither grows past the document limit nor gets traversed.
+    async fn refresh_cardinality(&self, filter: Document) -> Result<()> {
+        let max_payload_numbers = self.max_payload_numbers() as i64;
+        let size = doc! { "$size": "$payload_numbers" };
+        let capped = doc! { "$or": [
+            { "$eq": [{ "$ifNull": ["$payload_numbers_capped", false] }, true] },
+            { "$gt": [size.clone(), max_payload_numbers] },
+        ]};
+        self.match_nodes()
+            .update_one(
+                filter,
+                vec![doc! { "$set": {
+                    "payload_numbers": { "$slice": ["$payload_numbers", max_payload_numbers] },
+                    "payload_numbers_capped": capped.clone(),
+                    "cardinality": { "$toLong": { "$min": [size.clone(), max_payload_numbers] } },
+                    "is_hub": { "$or": [
+                        capped,
+                        { "$gte": [size, self.hub_config.threshold as i64] },
+                    ]},
+                }}],
+            )
+            .await?;
+        Ok(())
+    }
+
+    fn max_payload_numbers(&self) -> usize {
+        self.match_node_storage.max_payload_numbers.max(self.hub_config.threshold).max(1)
+    }
+
+    /// Drops a node, and its buckets, once no transaction links to it anymore. Capped nodes may
+    /// still have links of payload numbers they don't list, so they are kept.
+    async fn delete_if_unlinked(&self, filter: Document) -> Result<()> {
+        let mut unlinked = filter.clone();
+        unlinked.insert("payload_numbers", doc! { "$size": 0 });
+        unlinked.insert("payload_numbers_capped", doc! { "$ne": true });
+        let deleted = self.match_nodes().delete_one(unlinked).await?;
+        if deleted.deleted_count > 0 {
+            self.match_node_transactions().delete_many(filter).await?;
+        }
+        Ok(())
+    }
+
+    /// Appends links evicted from a node to its open bucket, opening a new one when all are full.
+    async fn save_overflow_links(
+        &self,
+        matcher: &str,
+        value: &str,
+        links: Vec<MatchNodeTransaction>,
+    ) -> Result<()> {
+        let count = links.len() as i64;
+        let bucket_size = self.match_node_storage.bucket_size.max(1) as i64;
+        self.match_node_transactions()
+            .update_one(
+                doc! { "matcher": matcher, "value": value, "count": { "$lte": bucket_size - count } },
+                doc! {
+                    "$push": { "transaction_data": { "$each": to_bson(&links)? } },
+                    "$inc": { "count": count },
+                },
+            )
+            .upsert(true)
+            .await?;
+        Ok(())
+    }
+
+    /// Payload numbers of bucketed links passing `link_condition`, for the nodes with overflow.
+    async fn find_bucket_links(
+        &self,
+        agg_results: &[AggregatedGraph],
+        link_condition: &Document,
+    ) -> Result<Vec<BucketLinks>> {
+        let mut seen = HashSet::new();
+        let nodes: Vec<Document> = agg_results
+            .iter()
+            .flat_map(|agg| {
+                std::iter::once((agg.overflow, &agg.matcher, &agg.value))
+                    .chain(agg.graph.iter().map(|node| (node.overflow, &node.matcher, &node.value)))
+            })
+            .filter(|(overflow, matcher, value)| *overflow && seen.insert((*matcher, *value)))
+            .map(|(_, matcher, value)| doc! { "matcher": matcher, "value": value })
+            .collect();
+        if nodes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let pipeline = vec![
+            doc! { "$match": { "$or": nodes } },
+            doc! { "$project": {
+                "_id": 0,
+                "matcher": 1,
+                "value": 1,
+                "payload_numbers": Self::linked_payload_numbers("$transaction_data", link_condition),
+            }},
+        ];
+        let docs: Vec<Document> = self
+            .match_node_transactions()
+            .aggregate(pipeline)
+            .await?
+            .try_collect()
+            .await?;
+        docs.into_iter()
+            .map(|doc| Ok(bson::from_document::<BucketLinks>(doc)?))
+            .collect()
+    }
+
+    /// Links pushed out of the inline window when one more is appended to `before`.
+    fn evicted_links(mut before: Vec<MatchNodeTransaction>, inline_links: usize) -> Vec<MatchNodeTransaction> {
+        let evicted = (before.len() + 1).saturating_sub(inline_links).min(before.len());
+        before.truncate(evicted);
+        before
+    }
+
+    /// `true` when a node saved more links than it keeps inline.
+    fn has_overflow(link_count: &str, links: &str) -> Document {
+        doc! { "$gt": [
+            { "$ifNull": [link_count, 0] },
+            { "$size": { "$ifNull": [links, []] } },
+        ]}
+    }
+
+    /// Adds the filtered payload numbers of bucketed links to their nodes.
+    fn merge_bucket_links(agg_results: &mut [AggregatedGraph], bucket_links: Vec<BucketLinks>) {
+        if bucket_links.is_empty() {
+            return;
+        }
+        let mut by_node: HashMap<(String, String), Vec<String>> = HashMap::new();
+        for links in bucket_links {
+            by_node
+                .entry((links.matcher, links.value))
+                .or_default()
+                .extend(links.payload_numbers);
+        }
+
+        let merge = |payload_numbers: &mut Vec<String>, matcher: &str, value: &str| {
+            if let Some(bucketed) = by_node.get(&(matcher.to_string(), value.to_string())) {
+                for payload_number in bucketed {
+                    if !payload_numbers.contains(payload_number) {
+                        payload_numbers.push(payload_number.clone());
+                    }
+                }
+            }
+        };
+        for agg in agg_results.iter_mut() {
+            if agg.overflow {
+                merge(&mut agg.payload_numbers, &agg.matcher, &agg.value);
+            }
+            for node in agg.graph.iter_mut().filter(|node| node.overflow) {
+                merge(&mut node.payload_numbers, &node.matcher, &node.value);
+            }
+        }
+    }
+
+    /// `confidence` lower bound, with per matcher overrides taking precedence over the default.
+    /// The confidence a node of `matcher` needs to be followed, the same rule as
+    /// `confidence_condition`.
+    fn min_confidence(traversal: &TraversalConfig, matcher: &str) -> i32 {
+        traversal
+            .matchers
+            .get(matcher)
+            .and_then(|config| config.min_confidence)
+            .unwrap_or(traversal.min_confidence)
+            .clamp(0, 100)
+    }
+
+    fn confidence_condition(traversal: &TraversalConfig) -> Document {
+        let default_min = doc! { "$gte": traversal.min_confidence.clamp(0, 100) };
+        let overrides: Vec<(&String, i32)> = traversal
+            .matchers
+            .iter()
+            .filter_map(|(matcher, config)| config.min_confidence.map(|min| (matcher, min.clamp(0, 100))))
+            .collect();
+        if overrides.is_empty() {
+            return doc! { "confidence": default_min };
+        }
+
+        let overridden: Vec<&String> = overrides.iter().map(|(matcher, _)| *matcher).collect();
+        let mut branches = vec![doc! { "matcher": { "$nin": overridden }, "confidence": default_min }];
+        branches.extend(
+            overrides
+                .into_iter()
+                .map(|(matcher, min)| doc! { "matcher": matcher, "confidence": { "$gte": min } }),
+        );
+        doc! { "$or": branches }
+    }
+
+    /// Aggregation condition over `$$node` (a `$graphLookup` result) that drops nodes found deeper
+    /// than their matcher allows. `None` when no matcher overrides the depth.
+    fn matcher_depth_condition(traversal: &TraversalConfig) -> Option<Document> {
+        let overrides: Vec<(&String, i32)> = traversal
+            .matchers
+            .iter()
+            .filter_map(|(matcher, config)| config.max_depth.map(|depth| (matcher, depth)))
+            .collect();
+        if overrides.is_empty() {
+            return None;
+        }
+
+        let overridden: Vec<&String> = overrides.iter().map(|(matcher, _)| *matcher).collect();
+        let mut branches = vec![doc! { "$not": [{ "$in": ["$$node.matcher", overridden] }] }];
+        branches.extend(overrides.into_iter().map(|(matcher, depth)| {
+            doc! { "$and": [
+                { "$eq": ["$$node.matcher", matcher] },
+                { "$lt": ["$$node.depth", depth] },
+            ]}
+        }));
+        Some(doc! { "$or": branches })
+    }
+
+    /// Matches nodes that are not hubs, taking the analyst override into account.
+    fn not_hub_condition() -> Document {
+        doc! { "$nor": [
+            { "hub_override": true },
+            { "hub_override": { "$ne": false }, "is_hub": true },
+        ]}
+    }
+
+    fn weighted_confidence(confidence: i32, weight: f64) -> i32 {
+        (confidence as f64 * weight.clamp(0.0, 1.0)).round() as i32
+    }
+
+    /// Scales the confidence of hub nodes, dropping the ones that fall below `min_confidence`.
+    fn down_weight_hubs(
+        agg_results: Vec<AggregatedGraph>,
+        weight: f64,
+        traversal: &TraversalConfig,
+    ) -> Vec<AggregatedGraph> {
+        agg_results
+            .into_iter()
+            .filter_map(|mut agg| {
+                if agg.hub_override.unwrap_or(agg.is_hub) {
+                    agg.confidence = Self::weighted_confidence(agg.confidence, weight);
+                    if agg.confidence < Self::min_confidence(traversal, &agg.matcher) {
+                        return None;
+                    }
+                }
+                agg.graph.retain_mut(|node| {
+                    if node.hub_override.unwrap_or(node.is_hub) {
+                        node.confidence = Self::weighted_confidence(node.confidence, weight);
+                    }
+                    node.confidence >= Self::min_confidence(traversal, &node.matcher)
+                });
+                Some(agg)
+            })
+            .collect()
+    }
+
+    fn matcher_condition(filter: &GraphFilterConfig) -> Option<Document> {
+        let mut condition = Document::new();
+        if let Some(allowed) = &filter.allowed_matchers {
+            condition.insert("$in", allowed.clone());
+        }
+        if !filter.denied_matchers.is_empty() {
+            condition.insert("$nin", filter.denied_matchers.clone());
+        }
+        (!condition.is_empty()).then_some(condition)
+    }
+
+    /// Aggregation condition over `$$link` (a `MatchNodeTransaction`) that keeps links within the
+    /// configured time and distance from the root. `None` when nothing has to be filtered.
+    fn link_condition(
+        filter: &GraphFilterConfig,
+        root_link: Option<&MatchNodeTransaction>,
+    ) -> Result<Option<Document>> {
+        let Some(root_link) = root_link else {
+            return Ok(None);
+        };
+        let mut conditions: Vec<Bson> = Vec::new();
+
+        if let Some(root_datetime) = root_link.datetime_alpha {
+            let unknown_time = doc! { "$eq": [{ "$ifNull": ["$$link.datetime_alpha", Bson::Null] }, Bson::Null] };
+            let link_datetime = Self::to_date("$$link.datetime_alpha");
+            if let Some(max_age) = filter.max_age_seconds {
+                let oldest = Self::naive_bson_datetime(root_datetime - chrono::Duration::seconds(max_age));
+                conditions.push(Bson::Document(doc! { "$or": [
+                    unknown_time.clone(),
+                    { "$gte": [link_datetime.clone(), oldest] },
+                ]}));
+            }
+            if let Some(window) = filter.root_time_window_seconds {
+                let from = Self::naive_bson_datetime(root_datetime - chrono::Duration::seconds(window));
+                let to = Self::naive_bson_datetime(root_datetime + chrono::Duration::seconds(window));
+                conditions.push(Bson::Document(doc! { "$or": [
+                    unknown_time,
+                    { "$and": [
+                        { "$gte": [link_datetime.clone(), from] },
+                        { "$lte": [link_datetime, to] },
+                    ]},
+                ]}));
+            }
+        }
+
+        if let (Some(max_distance_km), Some(root_lat), Some(root_long)) =
+            (filter.max_distance_km, root_link.lat_alpha, root_link.long_alpha)
+        {
+            conditions.push(Bson::Document(doc! { "$or": [
+                { "$eq": [{ "$ifNull": ["$$link.lat_alpha", Bson::Null] }, Bson::Null] },
+                { "$eq": [{ "$ifNull": ["$$link.long_alpha", Bson::Null] }, Bson::Null] },
+                { "$lte": [
+                    Self::haversine_km("$$link.lat_alpha", "$$link.long_alpha", root_lat, root_long),
+                    max_distance_km,
+                ]},
+            ]}));
+        }
+
+        Ok((!conditions.is_empty()).then(|| doc! { "$and": conditions }))
+    }
+
+    /// Link timestamps are stored as naive ISO strings, `$toDate` reads them as UTC so they
+    /// compare as dates rather than as text.
+    fn to_date(field: &str) -> Document {
+        doc! { "$toDate": field }
+    }
+
+    fn naive_bson_datetime(dt: NaiveDateTime) -> BsonDateTime {
+        bson_datetime(dt.and_utc())
+    }
+
+    /// Great-circle distance in km between the given fields and a fixed point.
+    fn haversine_km(lat_field: &str, long_field: &str, lat: f64, long: f64) -> Document {
+        let half_sin_squared = |delta: Document| {
+            doc! { "$pow": [{ "$sin": { "$divide": [delta, 2] } }, 2] }
+        };
+        let lat_delta = doc! { "$subtract": [{ "$degreesToRadians": lat_field }, lat.to_radians()] };
+        let long_delta = doc! { "$subtract": [{ "$degreesToRadians": long_field }, long.to_radians()] };
+        doc! { "$multiply": [
+            2.0 * EARTH_RADIUS_KM,
+            { "$asin": { "$sqrt": { "$add": [
+                half_sin_squared(lat_delta),
+                { "$multiply": [
+                    { "$cos": { "$degreesToRadians": lat_field } },
+                    lat.to_radians().cos(),
+                    half_sin_squared(long_delta),
+                ]},
+            ]}}},
+        ]}
+    }
+
+    /// Distinct payload numbers of the links in `links` that satisfy `link_condition`.
+    fn linked_payload_numbers(links: &str, link_condition: &Document) -> Document {
+        doc! { "$setUnion": [{ "$map": {
+            "input": { "$filter": { "input": links, "as": "link", "cond": link_condition.clone() } },
+            "as": "link",
+            "in": "$$link.payload_number",
+        }}]}
+    }
+
     /// Build connected transactions from aggregated graph results.
     fn collect_connections(
         root_payload: &str,
@@ -591,22 +1843,26 @@ impl MongoCommonStorage {
     ) -> Vec<ConnectedTransaction> {
         let mut connections: HashMap<String, ConnectedTransaction> = HashMap::new();
 
-        let mut process_node = |matcher: &str, confidence: i32, payloads: &[String]| {
+        // Root nodes link straight to the root, graph nodes only through an already connected
+        // payload; a graph node whose links were all filtered out is unreachable.
+        let mut process_node = |matcher: &str, confidence: i32, payloads: &[String], is_root_node: bool| {
             for target in payloads {
                 if target == root_payload {
                     continue;
                 }
 
-                let parent_opt = payloads
-                    .iter()
-                    .filter(|p| *p != target)
-                    .filter_map(|p| connections.get(p))
-                    .max_by(|a, b| a.total_confidence.cmp(&b.total_confidence));
-
-                let (base_path, base_conf) = if let Some(parent) = parent_opt {
-                    (parent.path.clone(), parent.total_confidence)
-                } else {
+                let (base_path, base_conf) = if is_root_node {
                     (Vec::new(), 100)
+                } else {
+                    let parent_opt = payloads
+                        .iter()
+                        .filter(|p| *p != target)
+                        .filter_map(|p| connections.get(p))
+                        .max_by(|a, b| a.total_confidence.cmp(&b.total_confidence));
+                    match parent_opt {
+                        Some(parent) => (parent.path.clone(), parent.total_confidence),
+                        None => continue,
+                    }
                 };
 
                 let mut new_path = base_path;
@@ -628,17 +1884,18 @@ impl MongoCommonStorage {
                         payload_number: target.clone(),
                         path: new_path.clone(),
                         total_confidence: new_conf,
+                        label: None,
                     });
             }
         };
 
         for agg in agg_results {
-            process_node(&agg.matcher, agg.confidence, &agg.payload_numbers);
+            process_node(&agg.matcher, agg.confidence, &agg.payload_numbers, true);
 
             let mut graph_nodes = agg.graph.clone();
             graph_nodes.sort_by(|a, b| a.depth.cmp(&b.depth));
             for node in graph_nodes {
-                process_node(&node.matcher, node.confidence, &node.payload_numbers);
+                process_node(&node.matcher, node.confidence, &node.payload_numbers, false);
             }
         }
 
@@ -660,6 +1917,8 @@ impl MongoCommonStorage {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use common::config::MatcherTraversalConfig;
+    use chrono::TimeZone;
 
     #[test]
     fn collect_connections_builds_paths_and_confidence() {
@@ -672,7 +1931,15 @@ mod tests {
                 confidence: 80,
                 payload_numbers: vec!["B".to_string(), "C".to_string()],
                 depth: 1,
+                value: "d-1".to_string(),
+                is_hub: false,
+                hub_override: None,
+                overflow: false,
             }],
+            value: "a@example.com".to_string(),
+            is_hub: false,
+            hub_override: None,
+            overflow: false,
         };
 
         let result = MongoCommonStorage::collect_connections("A", vec![agg], Some(10));
@@ -692,6 +1959,316 @@ mod tests {
         assert_eq!(c.path, vec!["email", "device"]);
         assert_eq!(c.total_confidence, 72); // 90 * 80 / 100
     }
+
+    #[test]
+    fn collect_connections_skips_graph_nodes_without_connected_payload() {
+        let agg = AggregatedGraph {
+            matcher: "email".to_string(),
+            confidence: 90,
+            payload_numbers: vec!["A".to_string(), "B".to_string()],
+            graph: vec![GraphNode {
+                // B's link to this node was filtered out, C is no longer reachable
+                matcher: "device".to_string(),
+                confidence: 80,
+                payload_numbers: vec!["C".to_string()],
+                depth: 0,
+                value: "d-1".to_string(),
+                is_hub: false,
+                hub_override: None,
+                overflow: false,
+            }],
+            value: "a@example.com".to_string(),
+            is_hub: false,
+            hub_override: None,
+            overflow: false,
+        };
+
+        let result = MongoCommonStorage::collect_connections("A", vec![agg], Some(10));
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].payload_number, "B");
+    }
+
+    #[test]
+    fn down_weight_hubs_respects_overrides_and_min_confidence() {
+        let node = |matcher: &str, confidence, is_hub, hub_override| GraphNode {
+            matcher: matcher.to_string(),
+            confidence,
+            payload_numbers: vec!["B".to_string()],
+            value: "v".to_string(),
+            depth: 0,
+            is_hub,
+            hub_override,
+            overflow: false,
+        };
+        let agg = |matcher: &str, is_hub, hub_override| AggregatedGraph {
+            matcher: matcher.to_string(),
+            confidence: 100,
+            payload_numbers: vec!["A".to_string(), "B".to_string()],
+            graph: vec![
+                node("ip", 100, true, None),
+                node("email", 100, true, Some(false)),
+                node("device", 60, false, Some(true)),
+            ],
+            value: "v".to_string(),
+            is_hub,
+            hub_override,
+            overflow: false,
+        };
+
+        let traversal = TraversalConfig { min_confidence: 40, ..TraversalConfig::default() };
+        let result = MongoCommonStorage::down_weight_hubs(
+            vec![agg("phone", false, None), agg("ip", true, None)],
+            0.5,
+            &traversal,
+        );
+
+        // The root hub falls to 50 and stays, the graph hub at 60 falls to 30 and is dropped
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].confidence, 100);
+        assert_eq!(result[1].confidence, 50);
+        let graph: Vec<(&str, i32)> = result[0]
+            .graph
+            .iter()
+            .map(|n| (n.matcher.as_str(), n.confidence))
+            .collect();
+        assert_eq!(graph, vec![("ip", 50), ("email", 100)]);
+
+        let dropped = MongoCommonStorage::down_weight_hubs(vec![agg("ip", false, Some(true))], 0.3, &traversal);
+        assert!(dropped.is_empty());
+    }
+
+    #[test]
+    fn down_weight_hubs_applies_matcher_min_confidence_overrides() {
+        let node = |matcher: &str| GraphNode {
+            matcher: matcher.to_string(),
+            confidence: 80,
+            payload_numbers: vec!["B".to_string()],
+            value: "v".to_string(),
+            depth: 0,
+            is_hub: true,
+            hub_override: None,
+            overflow: false,
+        };
+        let agg = AggregatedGraph {
+            matcher: "ip".to_string(),
+            confidence: 80,
+            payload_numbers: vec!["A".to_string(), "B".to_string()],
+            graph: vec![node("ip"), node("email")],
+            value: "v".to_string(),
+            is_hub: true,
+            hub_override: None,
+            overflow: false,
+        };
+        let mut traversal = TraversalConfig { min_confidence: 50, ..TraversalConfig::default() };
+        traversal
+            .matchers
+            .insert("ip".to_string(), MatcherTraversalConfig { min_confidence: Some(30), max_depth: None });
+        traversal
+            .matchers
+            .insert("email".to_string(), MatcherTraversalConfig { min_confidence: Some(60), max_depth: None });
+
+        // Both hubs fall to 40: enough for ip's lower override, not for email's higher one
+        let result = MongoCommonStorage::down_weight_hubs(vec![agg], 0.5, &traversal);
+
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].confidence, 40);
+        let graph: Vec<&str> = result[0].graph.iter().map(|n| n.matcher.as_str()).collect();
+        assert_eq!(graph, vec!["ip"]);
+    }
+
+    fn link(payload_number: &str) -> MatchNodeTransaction {
+        MatchNodeTransaction {
+            payload_number: payload_number.to_string(),
+            raw_value: None,
+            source_value: None,
+            datetime_alpha: None,
+            datetime_beta: None,
+            long_alpha: None,
+            lat_alpha: None,
+            long_beta: None,
+            lat_beta: None,
+            long_gamma: None,
+            lat_gamma: None,
+            long_delta: None,
+            lat_delta: None,
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn evicted_links_are_the_oldest_beyond_the_inline_window() {
+        let before = vec![link("A"), link("B"), link("C")];
+        let payload_numbers = |links: Vec<MatchNodeTransaction>| -> Vec<String> {
+            links.into_iter().map(|l| l.payload_number).collect()
+        };
+
+        assert_eq!(payload_numbers(MongoCommonStorage::evicted_links(before.clone(), 3)), vec!["A"]);
+        assert!(MongoCommonStorage::evicted_links(before.clone(), 4).is_empty());
+        assert_eq!(payload_numbers(MongoCommonStorage::evicted_links(before, 1)), vec!["A", "B", "C"]);
+        assert!(MongoCommonStorage::evicted_links(Vec::new(), 1).is_empty());
+    }
+
+    #[test]
+    fn merge_bucket_links_extends_only_overflowed_nodes() {
+        let mut agg = AggregatedGraph {
+            matcher: "email".to_string(),
+            value: "a@example.com".to_string(),
+            confidence: 90,
+            payload_numbers: vec!["A".to_string(), "B".to_string()],
+            graph: vec![GraphNode {
+                matcher: "device".to_string(),
+                value: "d-1".to_string(),
+                confidence: 80,
+                payload_numbers: vec!["B".to_string()],
+                depth: 0,
+                is_hub: false,
+                hub_override: None,
+                overflow: false,
+            }],
+            is_hub: false,
+            hub_override: None,
+            overflow: true,
+        };
+        let bucket_links = vec![
+            BucketLinks {
+                matcher: "email".to_string(),
+                value: "a@example.com".to_string(),
+                payload_numbers: vec!["B".to_string(), "OLD".to_string()],
+            },
+            BucketLinks {
+                matcher: "device".to_string(),
+                value: "d-1".to_string(),
+                payload_numbers: vec!["C".to_string()],
+            },
+        ];
+
+        MongoCommonStorage::merge_bucket_links(std::slice::from_mut(&mut agg), bucket_links);
+
+        assert_eq!(agg.payload_numbers, vec!["A", "B", "OLD"]);
+        assert_eq!(agg.graph[0].payload_numbers, vec!["B"]);
+    }
+
+    #[test]
+    fn graph_edges_pair_members_sharing_a_node() {
+        let members: HashMap<String, i32> =
+            HashMap::from([("A".to_string(), 0), ("B".to_string(), 1), ("C".to_string(), 1)]);
+        let node = |matcher: &str, payload_numbers: &[&str]| EdgeNodeDoc {
+            matcher: matcher.to_string(),
+            value: "v".to_string(),
+            confidence: 90,
+            payload_numbers: payload_numbers.iter().map(|p| p.to_string()).collect(),
+        };
+
+        // OUTSIDE was cut by the traversal and gets no edges
+        let edges = MongoCommonStorage::graph_edges(
+            vec![node("email", &["C", "A", "B"]), node("device", &["B", "OUTSIDE"])],
+            &members,
+        );
+
+        let pairs: Vec<(&str, &str)> = edges.iter().map(|e| (e.source.as_str(), e.target.as_str())).collect();
+        assert_eq!(pairs, vec![("A", "B"), ("A", "C"), ("B", "C")]);
+        assert!(edges.iter().all(|e| e.matcher == "email" && e.confidence == 90));
+    }
+
+    #[test]
+    fn graph_edges_of_large_nodes_link_members_to_the_closest() {
+        let mut members: HashMap<String, i32> = (0..50).map(|i| (format!("P{:02}", i), 2)).collect();
+        members.insert("P30".to_string(), 1);
+        let node = EdgeNodeDoc {
+            matcher: "email".to_string(),
+            value: "v".to_string(),
+            confidence: 90,
+            payload_numbers: members.keys().cloned().collect(),
+        };
+
+        let edges = MongoCommonStorage::graph_edges(vec![node], &members);
+
+        assert_eq!(edges.len(), 49);
+        assert!(edges.iter().all(|e| e.source == "P30" || e.target == "P30"));
+    }
+
+    #[test]
+    fn link_condition_is_empty_without_root_reference() {
+        let filter = GraphFilterConfig {
+            max_age_seconds: Some(3600),
+            max_distance_km: Some(10.0),
+            ..GraphFilterConfig::default()
+        };
+        assert!(MongoCommonStorage::link_condition(&filter, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn link_condition_compares_link_times_as_dates() {
+        let filter = GraphFilterConfig { max_age_seconds: Some(3600), ..GraphFilterConfig::default() };
+        let root_datetime = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let root_link = MatchNodeTransaction { datetime_alpha: Some(root_datetime.naive_utc()), ..link("A") };
+
+        let condition = MongoCommonStorage::link_condition(&filter, Some(&root_link)).unwrap().unwrap();
+        let oldest = BsonDateTime::from_millis((root_datetime - chrono::Duration::hours(1)).timestamp_millis());
+        assert_eq!(
+            condition,
+            doc! { "$and": [{ "$or": [
+                { "$eq": [{ "$ifNull": ["$$link.datetime_alpha", Bson::Null] }, Bson::Null] },
+                { "$gte": [{ "$toDate": "$$link.datetime_alpha" }, oldest] },
+            ]}]}
+        );
+    }
+
+    #[test]
+    fn root_time_window_is_centered_on_the_root() {
+        let filter = GraphFilterConfig { root_time_window_seconds: Some(600), ..GraphFilterConfig::default() };
+        let root_datetime = Utc.with_ymd_and_hms(2026, 3, 1, 12, 0, 0).unwrap();
+        let root_link = MatchNodeTransaction { datetime_alpha: Some(root_datetime.naive_utc()), ..link("A") };
+
+        let condition = MongoCommonStorage::link_condition(&filter, Some(&root_link)).unwrap().unwrap();
+        let millis = |offset: i64| BsonDateTime::from_millis((root_datetime + chrono::Duration::seconds(offset)).timestamp_millis());
+        assert_eq!(
+            condition,
+            doc! { "$and": [{ "$or": [
+                { "$eq": [{ "$ifNull": ["$$link.datetime_alpha", Bson::Null] }, Bson::Null] },
+                { "$and": [
+                    { "$gte": [{ "$toDate": "$$link.datetime_alpha" }, millis(-600)] },
+                    { "$lte": [{ "$toDate": "$$link.datetime_alpha" }, millis(600)] },
+                ]},
+            ]}]}
+        );
+    }
+
+    #[test]
+    fn confidence_condition_prefers_matcher_overrides() {
+        let mut traversal = TraversalConfig { min_confidence: 50, ..TraversalConfig::default() };
+        assert_eq!(
+            MongoCommonStorage::confidence_condition(&traversal),
+            doc! { "confidence": { "$gte": 50 } }
+        );
+
+        traversal.matchers.insert(
+            "ip.address".to_string(),
+            MatcherTraversalConfig { min_confidence: Some(30), max_depth: None },
+        );
+        assert_eq!(
+            MongoCommonStorage::confidence_condition(&traversal),
+            doc! { "$or": [
+                { "matcher": { "$nin": ["ip.address"] }, "confidence": { "$gte": 50 } },
+                { "matcher": "ip.address", "confidence": { "$gte": 30 } },
+            ]}
+        );
+        assert_eq!(MongoCommonStorage::matcher_depth_condition(&traversal), None);
+    }
+
+    #[test]
+    fn matcher_condition_combines_allow_and_deny_lists() {
+        let filter = GraphFilterConfig {
+            allowed_matchers: Some(vec!["email".to_string(), "device".to_string()]),
+            denied_matchers: vec!["device".to_string()],
+            ..GraphFilterConfig::default()
+        };
+        assert_eq!(
+            MongoCommonStorage::matcher_condition(&filter),
+            Some(doc! { "$in": ["email", "device"], "$nin": ["device"] })
+        );
+        assert_eq!(MongoCommonStorage::matcher_condition(&GraphFilterConfig::default()), None);
+    }
 }
 
 /*
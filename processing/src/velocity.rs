@@ -0,0 +1,223 @@
+use std::collections::HashSet;
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::model::{DirectConnection, Feature, FeatureValue, NeighborSnapshot, Neighborhood};
+
+/// A lookback window of velocity features, `name` is used as the feature name suffix.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VelocityWindow {
+    pub name: &'static str,
+    pub seconds: i64,
+}
+
+impl VelocityWindow {
+    pub const HOUR: Self = Self { name: "1h", seconds: 3_600 };
+    pub const DAY: Self = Self { name: "24h", seconds: 86_400 };
+    pub const WEEK: Self = Self { name: "7d", seconds: 7 * 86_400 };
+    pub const MONTH: Self = Self { name: "30d", seconds: 30 * 86_400 };
+
+    pub const ALL: [Self; 4] = [Self::HOUR, Self::DAY, Self::WEEK, Self::MONTH];
+
+    pub fn duration(&self) -> Duration {
+        Duration::seconds(self.seconds)
+    }
+}
+
+/// Counts and sums over the neighbors directly connected by a matcher, within a window before
+/// the transaction was first created. Both sides are placed by their first version, so
+/// recalculation and re-imports count the same neighbors; sums read the latest features of each
+/// neighbor. Neighbors created later and the transaction itself are never counted.
+pub struct Velocity<'a> {
+    neighborhood: &'a Neighborhood,
+    direct_connections: &'a [DirectConnection],
+}
+
+impl<'a> Velocity<'a> {
+    pub fn new(neighborhood: &'a Neighborhood, direct_connections: &'a [DirectConnection]) -> Self {
+        Self { neighborhood, direct_connections }
+    }
+
+    /// Neighbors connected by `matcher` within `window`, each counted once.
+    pub fn neighbors(&self, matcher: &str, window: VelocityWindow) -> Vec<&'a NeighborSnapshot> {
+        let until = self.neighborhood.created_at;
+        let since = until - window.duration();
+        let mut seen = HashSet::new();
+        self.direct_connections
+            .iter()
+            .filter(|connection| connection.matcher == matcher)
+            .filter(|connection| seen.insert(connection.payload_number.as_str()))
+            .filter_map(|connection| self.neighborhood.neighbors.get(&connection.payload_number))
+            .filter(|neighbor| in_window(neighbor.created_at, since, until))
+            .collect()
+    }
+
+    pub fn count(&self, matcher: &str, window: VelocityWindow) -> i64 {
+        self.neighbors(matcher, window).len() as i64
+    }
+
+    /// Sum of a numeric neighbor feature, neighbors without it count as zero.
+    pub fn sum(&self, matcher: &str, window: VelocityWindow, feature: &str) -> f64 {
+        self.neighbors(matcher, window)
+            .into_iter()
+            .filter_map(|neighbor| match neighbor.feature(feature)? {
+                FeatureValue::Int(v) => Some(*v as f64),
+                FeatureValue::Double(v) => Some(*v),
+                _ => None,
+            })
+            .sum()
+    }
+
+    /// Number of distinct values of a neighbor feature, list features contribute each element.
+    pub fn distinct(&self, matcher: &str, window: VelocityWindow, feature: &str) -> i64 {
+        let mut values = HashSet::new();
+        for neighbor in self.neighbors(matcher, window) {
+            match neighbor.feature(feature) {
+                Some(FeatureValue::String(v)) => {
+                    values.insert(v.clone());
+                }
+                Some(FeatureValue::StringList(v)) => values.extend(v.iter().cloned()),
+                Some(FeatureValue::Int(v)) => {
+                    values.insert(v.to_string());
+                }
+                Some(FeatureValue::IntList(v)) => values.extend(v.iter().map(|x| x.to_string())),
+                Some(FeatureValue::Bool(v)) => {
+                    values.insert(v.to_string());
+                }
+                _ => {}
+            }
+        }
+        values.len() as i64
+    }
+
+    /// `<prefix>_count_<window>` for every window.
+    pub fn count_features(&self, matcher: &str, prefix: &str) -> Vec<Feature> {
+        VelocityWindow::ALL
+            .iter()
+            .map(|window| int_feature(prefix, "count", window, self.count(matcher, *window)))
+            .collect()
+    }
+
+    /// `<prefix>_sum_<window>` of a neighbor feature for every window.
+    pub fn sum_features(&self, matcher: &str, feature: &str, prefix: &str) -> Vec<Feature> {
+        VelocityWindow::ALL
+            .iter()
+            .map(|window| Feature {
+                name: format!("{}_sum_{}", prefix, window.name),
+                value: Box::new(FeatureValue::Double(self.sum(matcher, *window, feature))),
+            })
+            .collect()
+    }
+
+    /// `<prefix>_distinct_<window>` of a neighbor feature for every window.
+    pub fn distinct_features(&self, matcher: &str, feature: &str, prefix: &str) -> Vec<Feature> {
+        VelocityWindow::ALL
+            .iter()
+            .map(|window| int_feature(prefix, "distinct", window, self.distinct(matcher, *window, feature)))
+            .collect()
+    }
+}
+
+fn in_window(created_at: NaiveDateTime, since: NaiveDateTime, until: NaiveDateTime) -> bool {
+    created_at > since && created_at <= until
+}
+
+fn int_feature(prefix: &str, kind: &str, window: &VelocityWindow, value: i64) -> Feature {
+    Feature {
+        name: format!("{}_{}_{}", prefix, kind, window.name),
+        value: Box::new(FeatureValue::Int(value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(hours_ago: i64) -> NaiveDateTime {
+        now() - Duration::hours(hours_ago)
+    }
+
+    fn now() -> NaiveDateTime {
+        chrono::DateTime::from_timestamp(1_760_000_000, 0).unwrap().naive_utc()
+    }
+
+    fn neighbor(payload_number: &str, hours_ago: i64, amount: f64, method: &str) -> NeighborSnapshot {
+        NeighborSnapshot {
+            payload_number: payload_number.to_string(),
+            created_at: at(hours_ago),
+            features: vec![
+                Feature { name: "amount".to_string(), value: Box::new(FeatureValue::Double(amount)) },
+                Feature { name: "payment_method".to_string(), value: Box::new(FeatureValue::String(method.to_string())) },
+            ],
+            label: None,
+        }
+    }
+
+    fn connection(payload_number: &str, matcher: &str) -> DirectConnection {
+        DirectConnection {
+            payload_number: payload_number.to_string(),
+            matcher: matcher.to_string(),
+            confidence: 100,
+            importance: 90,
+            distance_km: None,
+            label: None,
+        }
+    }
+
+    fn fixture() -> (Neighborhood, Vec<DirectConnection>) {
+        let neighborhood = Neighborhood::new(
+            now(),
+            vec![
+                neighbor("A", 0, 10.0, "CreditCard"),
+                neighbor("B", 5, 20.0, "Invoice"),
+                neighbor("C", 48, 40.0, "CreditCard"),
+                neighbor("D", 24 * 20, 80.0, "Paypal"),
+                neighbor("LATER", -1, 160.0, "Paypal"),
+            ],
+        );
+        let connections = vec![
+            connection("A", "customer.email"),
+            connection("A", "customer.email"),
+            connection("B", "customer.email"),
+            connection("C", "customer.email"),
+            connection("D", "customer.email"),
+            connection("LATER", "customer.email"),
+            connection("B", "deviceIdent.token"),
+        ];
+        (neighborhood, connections)
+    }
+
+    #[test]
+    fn counts_neighbors_per_matcher_and_window() {
+        let (neighborhood, connections) = fixture();
+        let velocity = Velocity::new(&neighborhood, &connections);
+
+        assert_eq!(velocity.count("customer.email", VelocityWindow::HOUR), 1);
+        assert_eq!(velocity.count("customer.email", VelocityWindow::DAY), 2);
+        assert_eq!(velocity.count("customer.email", VelocityWindow::WEEK), 3);
+        assert_eq!(velocity.count("customer.email", VelocityWindow::MONTH), 4);
+        assert_eq!(velocity.count("deviceIdent.token", VelocityWindow::MONTH), 1);
+        assert_eq!(velocity.count("phone.number", VelocityWindow::MONTH), 0);
+    }
+
+    #[test]
+    fn sums_and_distinct_values_of_neighbor_features() {
+        let (neighborhood, connections) = fixture();
+        let velocity = Velocity::new(&neighborhood, &connections);
+
+        assert_eq!(velocity.sum("customer.email", VelocityWindow::DAY, "amount"), 30.0);
+        assert_eq!(velocity.sum("customer.email", VelocityWindow::MONTH, "amount"), 150.0);
+        assert_eq!(velocity.distinct("customer.email", VelocityWindow::WEEK, "payment_method"), 2);
+        assert_eq!(velocity.distinct("customer.email", VelocityWindow::MONTH, "payment_method"), 3);
+
+        let names: Vec<String> = velocity
+            .count_features("customer.email", "email_orders")
+            .into_iter()
+            .map(|feature| feature.name)
+            .collect();
+        assert_eq!(
+            names,
+            vec!["email_orders_count_1h", "email_orders_count_24h", "email_orders_count_7d", "email_orders_count_30d"]
+        );
+    }
+}
@@ -122,6 +122,7 @@ impl Processible for TestPayload {
         &self,
         connected_transactions: &[ConnectedTransaction],
         direct_connections: &[DirectConnection],
+        _neighborhood: &Neighborhood,
     ) -> Vec<Feature> {
         let mut features = Vec::new();
 
@@ -225,8 +225,9 @@ async fn test_processor_connection_feature_extraction() {
 
     // Test graph feature extraction with connections
     let transaction = TestPayload::high_value();
+    let neighborhood = processing::model::Neighborhood::new(chrono::Utc::now().naive_utc(), vec![]);
     let graph_features =
-        transaction.extract_graph_features(&connected_transactions, &direct_connections);
+        transaction.extract_graph_features(&connected_transactions, &direct_connections, &neighborhood);
 
     // Verify connection count features are present
     let _connected_count_feature = graph_features
@@ -123,7 +123,8 @@ async fn test_processor_feature_extraction() {
     // Test graph feature extraction
     let empty_connected = Vec::new();
     let empty_direct = Vec::new();
-    let graph_features = transaction.extract_graph_features(&empty_connected, &empty_direct);
+    let neighborhood = processing::model::Neighborhood::new(chrono::Utc::now().naive_utc(), vec![]);
+    let graph_features = transaction.extract_graph_features(&empty_connected, &empty_direct, &neighborhood);
 
     // Should have multiple features for high-value transactions
     assert!(graph_features.len() >= 5); // is_high_value, connected_count, direct_count, amount, amounts, categories, created_at